@@ -0,0 +1,85 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{program::invoke, system_instruction};
+
+declare_id!("AmqurSnVy7HNSHhBnKZnCchAgkj9RvuzXhVMW5KrmH7w");
+
+// Pyth price account layout
+pub const PRICE_ACCOUNT_LEN: usize = 240;
+pub const MAGIC: u32 = 0xa1b2_c3d4;
+pub const PRICE_ACCOUNT_TYPE: u32 = 3;
+pub const STATUS_TRADING: u32 = 1;
+
+// Writes price feeds in the Pyth price account layout so USD-priced paywalls
+// can be tested locally. Only trusted by noice-solana builds with the
+// `mock-oracle` feature; never deploy it to a public cluster
+#[program]
+pub mod mock_pyth {
+    use super::*;
+
+    // Write a trading aggregate price, creating the feed account on first
+    // use. The feed keypair signs to create the account
+    pub fn write_price_feed(
+        ctx: Context<WritePriceFeed>,
+        price: i64,
+        conf: u64,
+        expo: i32,
+        publish_time: i64,
+    ) -> Result<()> {
+        let price_feed = &ctx.accounts.price_feed;
+        if price_feed.data_is_empty() {
+            let rent = Rent::get()?.minimum_balance(PRICE_ACCOUNT_LEN);
+            invoke(
+                &system_instruction::create_account(
+                    ctx.accounts.payer.key,
+                    price_feed.key,
+                    rent,
+                    PRICE_ACCOUNT_LEN as u64,
+                    &crate::ID,
+                ),
+                &[
+                    ctx.accounts.payer.to_account_info(),
+                    price_feed.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        } else if price_feed.owner != &crate::ID {
+            return err!(ErrorCode::InvalidPriceFeed);
+        }
+
+        crate::write_price_feed(
+            &mut price_feed.try_borrow_mut_data()?,
+            price,
+            conf,
+            expo,
+            publish_time,
+        );
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct WritePriceFeed<'info> {
+    #[account(mut)]
+    pub price_feed: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Price feed is not owned by this program")]
+    InvalidPriceFeed,
+}
+
+// Writes a trading aggregate price into Pyth price account data; also used
+// directly by unit tests
+pub fn write_price_feed(data: &mut [u8], price: i64, conf: u64, expo: i32, publish_time: i64) {
+    data[0..4].copy_from_slice(&MAGIC.to_le_bytes());
+    data[8..12].copy_from_slice(&PRICE_ACCOUNT_TYPE.to_le_bytes());
+    data[20..24].copy_from_slice(&expo.to_le_bytes());
+    data[96..104].copy_from_slice(&publish_time.to_le_bytes());
+    data[208..216].copy_from_slice(&price.to_le_bytes());
+    data[216..224].copy_from_slice(&conf.to_le_bytes());
+    data[224..228].copy_from_slice(&STATUS_TRADING.to_le_bytes());
+}
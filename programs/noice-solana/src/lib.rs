@@ -1,9 +1,68 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
-use anchor_lang::solana_program::system_instruction;
+use anchor_lang::solana_program::{
+    program::{invoke, invoke_signed},
+    system_instruction,
+};
+use anchor_spl::token::spl_token::native_mint;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_2022_extensions::{
+    spl_pod::optional_keys::OptionalNonZeroPubkey,
+    spl_token_metadata_interface::state::{Field, TokenMetadata},
+    token_metadata_initialize, token_metadata_update_field, TokenMetadataInitialize,
+    TokenMetadataUpdateField,
+};
+use anchor_spl::token_interface::{
+    self, Approve, CloseAccount, Mint, MintTo, Revoke, TokenAccount, TokenInterface,
+    TransferChecked,
+};
 
 declare_id!("");
 
+// Basis points denominator (100%)
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+// Time after which the sender can reclaim an unclaimed escrowed tip
+pub const TIP_ESCROW_TIMEOUT: i64 = 30 * 24 * 60 * 60;
+
+// Profile field limits, matching the max_len attributes on UserProfile
+pub const MAX_DISPLAY_NAME_LEN: usize = 32;
+pub const MAX_AVATAR_URI_LEN: usize = 200;
+pub const MAX_ACCEPTED_MINTS: usize = 8;
+
+// Maximum number of recipients in a revenue split
+pub const MAX_SPLIT_RECIPIENTS: usize = 10;
+
+// Maximum number of payees sharing a paywall's revenue
+pub const MAX_PAYWALL_PAYEES: usize = 5;
+
+// Number of top supporters kept on a recipient's leaderboard
+pub const LEADERBOARD_SIZE: usize = 10;
+
+// Maximum number of paywalls in a bundle
+pub const MAX_BUNDLE_PAYWALLS: usize = 10;
+
+// Maximum number of mints a paywall accepts besides its primary mint
+pub const MAX_PAYWALL_MINTS: usize = 4;
+
+// Maximum number of wallets granted access in one grant_access call
+pub const MAX_ACCESS_GRANTS: usize = 10;
+
+// Pyth oracle program owning the price feeds of USD-priced paywalls
+pub const PYTH_ORACLE_PROGRAM_ID: Pubkey =
+    anchor_lang::pubkey!("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH");
+
+// Oldest price a USD-priced unlock accepts, in seconds
+pub const MAX_PRICE_AGE: i64 = 60;
+
+// Widest price confidence interval a USD-priced unlock accepts, in basis points
+pub const MAX_PRICE_CONF_BPS: u64 = 200;
+
+// Longest refund window a paywall can offer
+pub const MAX_REFUND_WINDOW: i64 = 30 * 24 * 60 * 60;
+
+// Access pass metadata keys for the creator royalty, as read by marketplaces
+pub const ROYALTY_BPS_METADATA_KEY: &str = "seller_fee_basis_points";
+pub const CREATOR_METADATA_KEY: &str = "creator";
 
 #[program]
 pub mod noice_solana {
@@ -18,6 +77,133 @@ pub mod noice_solana {
         Ok(())
     }
 
+    // Update the profile details shown to other users
+    pub fn update_profile(
+        ctx: Context<UpdateProfile>,
+        display_name: String,
+        avatar_uri: String,
+        bio_hash: [u8; 32],
+        accepted_mints: Vec<Pubkey>,
+    ) -> Result<()> {
+        if display_name.len() > MAX_DISPLAY_NAME_LEN {
+            return err!(ErrorCode::DisplayNameTooLong);
+        }
+        if avatar_uri.len() > MAX_AVATAR_URI_LEN {
+            return err!(ErrorCode::AvatarUriTooLong);
+        }
+        if accepted_mints.len() > MAX_ACCEPTED_MINTS {
+            return err!(ErrorCode::TooManyAcceptedMints);
+        }
+
+        let user_profile = &mut ctx.accounts.user_profile;
+        user_profile.display_name = display_name;
+        user_profile.avatar_uri = avatar_uri;
+        user_profile.bio_hash = bio_hash;
+        user_profile.accepted_mints = accepted_mints;
+
+        emit!(ProfileUpdated {
+            owner: user_profile.owner,
+            display_name: user_profile.display_name.clone(),
+            avatar_uri: user_profile.avatar_uri.clone(),
+            bio_hash,
+            accepted_mints: user_profile.accepted_mints.clone(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Updated user profile for: {}", user_profile.owner);
+        Ok(())
+    }
+
+    // Initialize the global platform config; must be signed by the program's
+    // upgrade authority, who becomes the admin
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        fee_bps: u16,
+        fee_recipient: Pubkey,
+        max_tip_amount: u64,
+        max_paywall_price: u64,
+    ) -> Result<()> {
+        if fee_bps as u64 > BPS_DENOMINATOR {
+            return err!(ErrorCode::InvalidFeeBps);
+        }
+
+        let platform_config = &mut ctx.accounts.platform_config;
+        platform_config.admin = ctx.accounts.admin.key();
+        platform_config.pending_admin = Pubkey::default();
+        platform_config.paused = false;
+        platform_config.fee_bps = fee_bps;
+        platform_config.fee_recipient = fee_recipient;
+        platform_config.max_tip_amount = max_tip_amount;
+        platform_config.max_paywall_price = max_paywall_price;
+        msg!(
+            "Initialized platform config with fee {} bps to {}",
+            fee_bps,
+            fee_recipient
+        );
+        Ok(())
+    }
+
+    // Update the platform fee and fee recipient
+    pub fn update_fee_config(
+        ctx: Context<UpdateConfig>,
+        fee_bps: u16,
+        fee_recipient: Pubkey,
+    ) -> Result<()> {
+        if fee_bps as u64 > BPS_DENOMINATOR {
+            return err!(ErrorCode::InvalidFeeBps);
+        }
+
+        let platform_config = &mut ctx.accounts.platform_config;
+        platform_config.fee_bps = fee_bps;
+        platform_config.fee_recipient = fee_recipient;
+        msg!(
+            "Updated platform fee to {} bps to {}",
+            fee_bps,
+            fee_recipient
+        );
+        Ok(())
+    }
+
+    // Update the per-instruction amount limits (0 means no limit)
+    pub fn update_limits(
+        ctx: Context<UpdateConfig>,
+        max_tip_amount: u64,
+        max_paywall_price: u64,
+    ) -> Result<()> {
+        let platform_config = &mut ctx.accounts.platform_config;
+        platform_config.max_tip_amount = max_tip_amount;
+        platform_config.max_paywall_price = max_paywall_price;
+        msg!(
+            "Updated limits: max tip {}, max paywall price {}",
+            max_tip_amount,
+            max_paywall_price
+        );
+        Ok(())
+    }
+
+    // Pause or resume tips and paywalls
+    pub fn set_paused(ctx: Context<UpdateConfig>, paused: bool) -> Result<()> {
+        ctx.accounts.platform_config.paused = paused;
+        msg!("Set paused to {}", paused);
+        Ok(())
+    }
+
+    // Propose a new admin; takes effect once they call accept_admin
+    pub fn transfer_admin(ctx: Context<UpdateConfig>, new_admin: Pubkey) -> Result<()> {
+        ctx.accounts.platform_config.pending_admin = new_admin;
+        msg!("Proposed new admin: {}", new_admin);
+        Ok(())
+    }
+
+    // Accept a pending admin handover
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        let platform_config = &mut ctx.accounts.platform_config;
+        platform_config.admin = ctx.accounts.pending_admin.key();
+        platform_config.pending_admin = Pubkey::default();
+        msg!("Admin transferred to {}", platform_config.admin);
+        Ok(())
+    }
+
     // Tip with any SPL token
     pub fn tip(
         ctx: Context<Tip>,
@@ -25,33 +211,88 @@ pub mod noice_solana {
         action: String,
         _token_mint: Pubkey, // Passed for validation
     ) -> Result<()> {
+        let max_tip_amount = ctx.accounts.platform_config.max_tip_amount;
+        if max_tip_amount > 0 && amount > max_tip_amount {
+            return err!(ErrorCode::AmountExceedsLimit);
+        }
+
         let user_profile = &mut ctx.accounts.recipient_profile;
         user_profile.interaction_count += 1;
 
-        // Validate token mint matches sender and recipient token accounts
+        // Validate token mint matches sender, recipient and fee token accounts
         if ctx.accounts.sender_token_account.mint != ctx.accounts.token_mint.key()
             || ctx.accounts.recipient_token_account.mint != ctx.accounts.token_mint.key()
+            || ctx.accounts.fee_token_account.mint != ctx.accounts.token_mint.key()
         {
             return err!(ErrorCode::InvalidTokenMint);
         }
 
+        // Split the tip into platform fee and recipient share
+        let fee = calculate_fee(amount, ctx.accounts.platform_config.fee_bps)?;
+        let recipient_amount = amount - fee;
+
         // Transfer tokens
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.sender_token_account.to_account_info(),
-            to: ctx.accounts.recipient_token_account.to_account_info(),
-            authority: ctx.accounts.sender.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
+        let net_amount = transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.sender_token_account,
+            &mut ctx.accounts.recipient_token_account,
+            &ctx.accounts.sender,
+            &ctx.accounts.token_mint,
+            recipient_amount,
+        )?;
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.sender_token_account,
+            &mut ctx.accounts.fee_token_account,
+            &ctx.accounts.sender,
+            &ctx.accounts.token_mint,
+            fee,
+        )?;
+
+        // Update per-mint stats for both parties
+        let now = Clock::get()?.unix_timestamp;
+        let token_mint = ctx.accounts.token_mint.key();
+        ctx.accounts.sender_stats.record_sent(
+            ctx.accounts.sender.key(),
+            token_mint,
+            amount,
+            now,
+        )?;
+        ctx.accounts.recipient_stats.record_received(
+            ctx.accounts.recipient.key(),
+            token_mint,
+            amount,
+            now,
+        )?;
+
+        // Update the sender's running total and the recipient's leaderboard
+        let supporter_total = &mut ctx.accounts.supporter_total;
+        if supporter_total.supporter == Pubkey::default() {
+            supporter_total.recipient = ctx.accounts.recipient.key();
+            supporter_total.supporter = ctx.accounts.sender.key();
+            supporter_total.token_mint = token_mint;
+        }
+        supporter_total.total = supporter_total
+            .total
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        if leaderboard.recipient == Pubkey::default() {
+            leaderboard.recipient = ctx.accounts.recipient.key();
+            leaderboard.token_mint = token_mint;
+        }
+        leaderboard.record(supporter_total.supporter, supporter_total.total);
 
         // Emit event for frontend
         emit!(TipEvent {
             sender: ctx.accounts.sender.key(),
             recipient: ctx.accounts.recipient.key(),
-            token_mint: ctx.accounts.token_mint.key(),
+            token_mint,
             amount,
-            action,
-            timestamp: Clock::get()?.unix_timestamp,
+            fee,
+            net_amount,
+            action: action.clone(),
+            timestamp: now,
         });
 
         msg!(
@@ -64,182 +305,4718 @@ pub mod noice_solana {
         Ok(())
     }
 
-    // Create a paywall for content
-    pub fn create_paywall(
-        ctx: Context<CreatePaywall>,
-        content_id: String,
-        price: u64,
-        token_mint: Pubkey,
+    // Tip with native SOL
+    pub fn tip_sol(ctx: Context<TipSol>, amount: u64, action: String) -> Result<()> {
+        let max_tip_amount = ctx.accounts.platform_config.max_tip_amount;
+        if max_tip_amount > 0 && amount > max_tip_amount {
+            return err!(ErrorCode::AmountExceedsLimit);
+        }
+
+        let user_profile = &mut ctx.accounts.recipient_profile;
+        user_profile.interaction_count += 1;
+
+        // Split the tip into platform fee and recipient share
+        let fee = calculate_fee(amount, ctx.accounts.platform_config.fee_bps)?;
+        let recipient_amount = amount - fee;
+
+        // Transfer lamports
+        transfer_lamports(
+            &ctx.accounts.sender,
+            &ctx.accounts.recipient,
+            &ctx.accounts.system_program,
+            recipient_amount,
+        )?;
+        transfer_lamports(
+            &ctx.accounts.sender,
+            &ctx.accounts.fee_recipient,
+            &ctx.accounts.system_program,
+            fee,
+        )?;
+
+        // Emit event for frontend, using the native mint as the SOL sentinel
+        emit!(TipEvent {
+            sender: ctx.accounts.sender.key(),
+            recipient: ctx.accounts.recipient.key(),
+            token_mint: native_mint::ID,
+            amount,
+            fee,
+            net_amount: recipient_amount,
+            action: action.clone(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Tipped {} lamports for {} to {}",
+            amount,
+            action,
+            ctx.accounts.recipient.key()
+        );
+        Ok(())
+    }
+
+    // Create a revenue split shared by several collaborators
+    pub fn create_revenue_split(
+        ctx: Context<CreateRevenueSplit>,
+        split_id: String,
+        recipients: Vec<SplitShare>,
     ) -> Result<()> {
-        let paywall = &mut ctx.accounts.paywall;
-        paywall.creator = ctx.accounts.creator.key();
-        paywall.content_id = content_id.clone();
-        paywall.price = price;
-        paywall.token_mint = token_mint;
-        paywall.access_count = 0;
+        validate_split_shares(&recipients)?;
+
+        let revenue_split = &mut ctx.accounts.revenue_split;
+        revenue_split.authority = ctx.accounts.authority.key();
+        revenue_split.split_id = split_id.clone();
+        revenue_split.recipients = recipients;
         msg!(
-            "Created paywall for content {} with price {} ({})",
-            content_id,
-            price,
-            token_mint
+            "Created revenue split {} with {} recipients",
+            split_id,
+            revenue_split.recipients.len()
         );
         Ok(())
     }
 
-    // Unlock paywall by paying with the specified token
-    pub fn unlock_paywall(ctx: Context<UnlockPaywall>, content_id: String) -> Result<()> {
-        let paywall = &mut ctx.accounts.paywall;
-        let amount = paywall.price;
+    // Tip a revenue split; recipient token accounts are passed as remaining
+    // accounts in the same order as the split's recipients
+    pub fn tip_split<'info>(
+        ctx: Context<'_, '_, 'info, 'info, TipSplit<'info>>,
+        amount: u64,
+        action: String,
+    ) -> Result<()> {
+        let max_tip_amount = ctx.accounts.platform_config.max_tip_amount;
+        if max_tip_amount > 0 && amount > max_tip_amount {
+            return err!(ErrorCode::AmountExceedsLimit);
+        }
 
-        // Validate token mint matches paywall and token accounts
-        if paywall.token_mint != ctx.accounts.token_mint.key()
-            || ctx.accounts.user_token_account.mint != ctx.accounts.token_mint.key()
-            || ctx.accounts.creator_token_account.mint != ctx.accounts.token_mint.key()
+        // Validate token mint matches sender and fee token accounts
+        if ctx.accounts.sender_token_account.mint != ctx.accounts.token_mint.key()
+            || ctx.accounts.fee_token_account.mint != ctx.accounts.token_mint.key()
         {
             return err!(ErrorCode::InvalidTokenMint);
         }
 
-        // Transfer tokens to creator
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.user_token_account.to_account_info(),
-            to: ctx.accounts.creator_token_account.to_account_info(),
-            authority: ctx.accounts.user.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
+        let recipients = &ctx.accounts.revenue_split.recipients;
+        let recipient_token_accounts = load_share_accounts(
+            recipients,
+            ctx.remaining_accounts,
+            &ctx.accounts.token_mint.key(),
+        )?;
 
-        // Update paywall access count
-        paywall.access_count += 1;
+        // Take the platform fee, then divide the rest by share with the
+        // rounding dust going to the first recipient
+        let fee = calculate_fee(amount, ctx.accounts.platform_config.fee_bps)?;
+        let shares: Vec<u16> = recipients.iter().map(|r| r.share_bps).collect();
+        let (mut amounts, dust) = split_amount(amount - fee, &shares)?;
+        amounts[0] += dust;
 
-        // Emit event
-        emit!(PaywallUnlockEvent {
-            user: ctx.accounts.user.key(),
-            creator: paywall.creator,
-            content_id,
-            token_mint: paywall.token_mint,
+        let mut payments = Vec::with_capacity(recipients.len());
+        for ((share, share_amount), mut recipient_token_account) in
+            recipients.iter().zip(amounts).zip(recipient_token_accounts)
+        {
+            let net_amount = transfer_tokens(
+                &ctx.accounts.token_program,
+                &ctx.accounts.sender_token_account,
+                &mut recipient_token_account,
+                &ctx.accounts.sender,
+                &ctx.accounts.token_mint,
+                share_amount,
+            )?;
+            payments.push(SplitPayment {
+                recipient: share.recipient,
+                amount: share_amount,
+                net_amount,
+            });
+        }
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.sender_token_account,
+            &mut ctx.accounts.fee_token_account,
+            &ctx.accounts.sender,
+            &ctx.accounts.token_mint,
+            fee,
+        )?;
+
+        emit!(TipSplitEvent {
+            sender: ctx.accounts.sender.key(),
+            revenue_split: ctx.accounts.revenue_split.key(),
+            token_mint: ctx.accounts.token_mint.key(),
             amount,
+            fee,
+            payments,
+            action: action.clone(),
             timestamp: Clock::get()?.unix_timestamp,
         });
 
         msg!(
-            "Unlocked paywall for content {} by {}",
-            paywall.content_id,
-            ctx.accounts.user.key()
+            "Tipped {} tokens ({}) for {} to split {}",
+            amount,
+            ctx.accounts.token_mint.key(),
+            action,
+            ctx.accounts.revenue_split.split_id
         );
         Ok(())
     }
-}
 
-// Account structures
-#[derive(Accounts)]
-pub struct InitializeUser<'info> {
-    #[account(
-        init,
-        payer = user,
-        space = 8 + 32 + 8 + 100, // Discriminator + Pubkey + u64 + padding
-        seeds = [b"user_profile", user.key().as_ref()],
-        bump
-    )]
-    pub user_profile: Account<'info, UserProfile>,
-    #[account(mut)]
-    pub user: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+    // Pledge a fixed tip every interval, approving the pledge PDA as delegate
+    // on the sender's token account for up to `max_periods` payments. A token
+    // account has a single delegate, so it can only back one pledge at a time
+    pub fn create_pledge(
+        ctx: Context<CreatePledge>,
+        amount: u64,
+        interval_seconds: i64,
+        max_periods: u32,
+    ) -> Result<()> {
+        if interval_seconds <= 0 || max_periods == 0 {
+            return err!(ErrorCode::InvalidPeriod);
+        }
+        let max_tip_amount = ctx.accounts.platform_config.max_tip_amount;
+        if max_tip_amount > 0 && amount > max_tip_amount {
+            return err!(ErrorCode::AmountExceedsLimit);
+        }
+        if ctx.accounts.sender_token_account.mint != ctx.accounts.token_mint.key() {
+            return err!(ErrorCode::InvalidTokenMint);
+        }
 
-#[derive(Accounts)]
-pub struct Tip<'info> {
-    #[account(
-        mut,
-        seeds = [b"user_profile", recipient.key().as_ref()],
-        bump
-    )]
-    pub recipient_profile: Account<'info, UserProfile>,
-    #[account(mut)]
-    pub sender_token_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub recipient_token_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub sender: Signer<'info>,
-    pub recipient: AccountInfo<'info>,
-    pub token_mint: AccountInfo<'info>, // Token mint for the SPL token
-    pub token_program: Program<'info, Token>,
-}
+        let allowance = amount
+            .checked_mul(max_periods as u64)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let cpi_accounts = Approve {
+            to: ctx.accounts.sender_token_account.to_account_info(),
+            delegate: ctx.accounts.pledge.to_account_info(),
+            authority: ctx.accounts.sender.to_account_info(),
+        };
+        token_interface::approve(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+            allowance,
+        )?;
 
-#[derive(Accounts)]
-#[instruction(content_id: String)]
-pub struct CreatePaywall<'info> {
-    #[account(
-        init,
-        payer = creator,
-        space = 8 + 32 + 32 + 8 + 32 + 8 + 100, // Discriminator + Pubkey + String + u64 + Pubkey + u64 + padding
-        seeds = [b"paywall", creator.key().as_ref(), content_id.as_bytes()],
-        bump
-    )]
-    pub paywall: Account<'info, Paywall>,
-    #[account(mut)]
-    pub creator: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+        let now = Clock::get()?.unix_timestamp;
+        let pledge = &mut ctx.accounts.pledge;
+        pledge.sender = ctx.accounts.sender.key();
+        pledge.recipient = ctx.accounts.recipient.key();
+        pledge.token_mint = ctx.accounts.token_mint.key();
+        pledge.sender_token_account = ctx.accounts.sender_token_account.key();
+        pledge.amount = amount;
+        pledge.interval_seconds = interval_seconds;
+        pledge.next_due_at = now;
+        pledge.executed_count = 0;
+        pledge.missed_periods = 0;
+        pledge.bump = ctx.bumps.pledge;
 
-#[derive(Accounts)]
-#[instruction(content_id: String)]
-pub struct UnlockPaywall<'info> {
-    #[account(
-        mut,
-        seeds = [b"paywall", paywall.creator.as_ref(), content_id.as_bytes()],
-        bump
-    )]
-    pub paywall: Account<'info, Paywall>,
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub creator_token_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub user: Signer<'info>,
-    pub token_mint: AccountInfo<'info>, // Token mint for the SPL token
-    pub token_program: Program<'info, Token>,
-}
+        emit!(PledgeCreated {
+            sender: pledge.sender,
+            recipient: pledge.recipient,
+            token_mint: pledge.token_mint,
+            amount,
+            interval_seconds,
+            max_periods,
+            timestamp: now,
+        });
 
-// Data structures
-#[account]
-pub struct UserProfile {
-    pub owner: Pubkey,          // User's public key
-    pub interaction_count: u64, // Number of interactions (tips received)
-}
+        msg!(
+            "Created pledge of {} tokens ({}) every {} seconds to {}",
+            amount,
+            pledge.token_mint,
+            interval_seconds,
+            pledge.recipient
+        );
+        Ok(())
+    }
 
-#[account]
-pub struct Paywall {
-    pub creator: Pubkey,      // Creator's public key
-    pub content_id: String,   // Unique content identifier
-    pub price: u64,          // Price in tokens
-    pub token_mint: Pubkey,   // SPL token mint for payments
-    pub access_count: u64,    // Number of users who unlocked
-}
+    // Pay a due pledge using the delegated authority; anyone can call this.
+    // Periods that passed without an execution are counted as missed
+    pub fn execute_pledge(ctx: Context<ExecutePledge>) -> Result<()> {
+        let pledge = &ctx.accounts.pledge;
+        let now = Clock::get()?.unix_timestamp;
+        if now < pledge.next_due_at {
+            return err!(ErrorCode::PledgeNotDue);
+        }
+        if ctx.accounts.recipient_token_account.mint != pledge.token_mint
+            || ctx.accounts.fee_token_account.mint != pledge.token_mint
+        {
+            return err!(ErrorCode::InvalidTokenMint);
+        }
 
-// Events for frontend integration
-#[event]
-pub struct TipEvent {
-    pub sender: Pubkey,
-    pub recipient: Pubkey,
-    pub token_mint: Pubkey,
-    pub amount: u64,
-    pub action: String,
-    pub timestamp: i64,
-}
+        // Split the tip into platform fee and recipient share
+        let amount = pledge.amount;
+        let fee = calculate_fee(amount, ctx.accounts.platform_config.fee_bps)?;
+        let recipient_amount = amount - fee;
 
-#[event]
-pub struct PaywallUnlockEvent {
-    pub user: Pubkey,
-    pub creator: Pubkey,
-    pub content_id: String,
-    pub token_mint: Pubkey,
-    pub amount: u64,
-    pub timestamp: i64,
-}
+        let sender_key = pledge.sender;
+        let recipient_key = pledge.recipient;
+        let mint_key = pledge.token_mint;
+        let seeds: &[&[u8]] = &[
+            b"pledge",
+            sender_key.as_ref(),
+            recipient_key.as_ref(),
+            mint_key.as_ref(),
+            &[pledge.bump],
+        ];
+        let pledge_info = pledge.to_account_info();
 
-// Custom errors
-#[error_code]
-pub enum ErrorCode {
-    #[msg("Invalid token mint provided")]
-    InvalidTokenMint,
+        // Transfer tokens as the delegate
+        let net_amount = transfer_tokens_signed(
+            &ctx.accounts.token_program,
+            &ctx.accounts.sender_token_account,
+            &mut ctx.accounts.recipient_token_account,
+            &pledge_info,
+            &ctx.accounts.token_mint,
+            recipient_amount,
+            &[seeds],
+        )?;
+        transfer_tokens_signed(
+            &ctx.accounts.token_program,
+            &ctx.accounts.sender_token_account,
+            &mut ctx.accounts.fee_token_account,
+            &pledge_info,
+            &ctx.accounts.token_mint,
+            fee,
+            &[seeds],
+        )?;
+
+        ctx.accounts.recipient_profile.interaction_count += 1;
+
+        let pledge = &mut ctx.accounts.pledge;
+        let missed = (now - pledge.next_due_at) / pledge.interval_seconds;
+        pledge.missed_periods += missed as u64;
+        pledge.executed_count += 1;
+        pledge.next_due_at += (missed + 1) * pledge.interval_seconds;
+
+        emit!(TipEvent {
+            sender: sender_key,
+            recipient: recipient_key,
+            token_mint: mint_key,
+            amount,
+            fee,
+            net_amount,
+            action: "pledge".to_string(),
+            timestamp: now,
+        });
+
+        msg!(
+            "Executed pledge of {} tokens ({}) to {}, {} periods missed",
+            amount,
+            mint_key,
+            recipient_key,
+            missed
+        );
+        Ok(())
+    }
+
+    // Cancel a pledge, revoking the delegate approval and returning rent
+    pub fn cancel_pledge(ctx: Context<CancelPledge>) -> Result<()> {
+        // Leave the delegate alone if the pledge no longer holds it, e.g.
+        // after its allowance was used up
+        let delegate: Option<Pubkey> = ctx.accounts.sender_token_account.delegate.into();
+        if delegate == Some(ctx.accounts.pledge.key()) {
+            let cpi_accounts = Revoke {
+                source: ctx.accounts.sender_token_account.to_account_info(),
+                authority: ctx.accounts.sender.to_account_info(),
+            };
+            token_interface::revoke(CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+            ))?;
+        }
+
+        let pledge = &ctx.accounts.pledge;
+        emit!(PledgeCancelled {
+            sender: pledge.sender,
+            recipient: pledge.recipient,
+            token_mint: pledge.token_mint,
+            executed_count: pledge.executed_count,
+            missed_periods: pledge.missed_periods,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Cancelled pledge to {}", pledge.recipient);
+        Ok(())
+    }
+
+    // Tip into an escrow vault the recipient can claim later, for
+    // recipients without a profile or token account yet
+    pub fn escrow_tip(ctx: Context<EscrowTip>, amount: u64, action: String) -> Result<()> {
+        let max_tip_amount = ctx.accounts.platform_config.max_tip_amount;
+        if max_tip_amount > 0 && amount > max_tip_amount {
+            return err!(ErrorCode::AmountExceedsLimit);
+        }
+
+        // Validate token mint matches sender token account
+        if ctx.accounts.sender_token_account.mint != ctx.accounts.token_mint.key() {
+            return err!(ErrorCode::InvalidTokenMint);
+        }
+
+        // Transfer the full amount into the vault; the fee is taken on claim
+        let net_amount = transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.sender_token_account,
+            &mut ctx.accounts.tip_vault,
+            &ctx.accounts.sender,
+            &ctx.accounts.token_mint,
+            amount,
+        )?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let tip_escrow = &mut ctx.accounts.tip_escrow;
+        tip_escrow.sender = ctx.accounts.sender.key();
+        tip_escrow.recipient = ctx.accounts.recipient.key();
+        tip_escrow.token_mint = ctx.accounts.token_mint.key();
+        tip_escrow.amount = tip_escrow
+            .amount
+            .checked_add(net_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        tip_escrow.expires_at = now + TIP_ESCROW_TIMEOUT;
+        tip_escrow.bump = ctx.bumps.tip_escrow;
+
+        emit!(TipEscrowed {
+            sender: tip_escrow.sender,
+            recipient: tip_escrow.recipient,
+            token_mint: tip_escrow.token_mint,
+            amount: net_amount,
+            action: action.clone(),
+            expires_at: tip_escrow.expires_at,
+            timestamp: now,
+        });
+
+        msg!(
+            "Escrowed {} tokens ({}) for {} to {}",
+            amount,
+            tip_escrow.token_mint,
+            action,
+            tip_escrow.recipient
+        );
+        Ok(())
+    }
+
+    // Claim escrowed tips from a sender; rent goes back to the sender
+    pub fn claim_tips(ctx: Context<ClaimTips>) -> Result<()> {
+        let tip_escrow = &ctx.accounts.tip_escrow;
+        let amount = ctx.accounts.tip_vault.amount;
+
+        // Validate token mint matches recipient and fee token accounts
+        if ctx.accounts.recipient_token_account.mint != tip_escrow.token_mint
+            || ctx.accounts.fee_token_account.mint != tip_escrow.token_mint
+        {
+            return err!(ErrorCode::InvalidTokenMint);
+        }
+
+        // Split the claim into platform fee and recipient share
+        let fee = calculate_fee(amount, ctx.accounts.platform_config.fee_bps)?;
+        let recipient_amount = amount - fee;
+
+        let sender_key = tip_escrow.sender;
+        let recipient_key = tip_escrow.recipient;
+        let mint_key = tip_escrow.token_mint;
+        let seeds: &[&[u8]] = &[
+            b"tip_escrow",
+            recipient_key.as_ref(),
+            mint_key.as_ref(),
+            sender_key.as_ref(),
+            &[tip_escrow.bump],
+        ];
+        let escrow_info = tip_escrow.to_account_info();
+
+        // Release the vault to the recipient and platform, then close it
+        let net_amount = transfer_tokens_signed(
+            &ctx.accounts.token_program,
+            &ctx.accounts.tip_vault,
+            &mut ctx.accounts.recipient_token_account,
+            &escrow_info,
+            &ctx.accounts.token_mint,
+            recipient_amount,
+            &[seeds],
+        )?;
+        transfer_tokens_signed(
+            &ctx.accounts.token_program,
+            &ctx.accounts.tip_vault,
+            &mut ctx.accounts.fee_token_account,
+            &escrow_info,
+            &ctx.accounts.token_mint,
+            fee,
+            &[seeds],
+        )?;
+        close_token_account(
+            &ctx.accounts.token_program,
+            &ctx.accounts.tip_vault,
+            &ctx.accounts.sender,
+            &escrow_info,
+            &[seeds],
+        )?;
+
+        emit!(TipsClaimed {
+            sender: sender_key,
+            recipient: recipient_key,
+            token_mint: mint_key,
+            amount,
+            fee,
+            net_amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Claimed {} escrowed tokens ({}) from {} by {}",
+            amount,
+            mint_key,
+            sender_key,
+            recipient_key
+        );
+        Ok(())
+    }
+
+    // Return an unclaimed escrowed tip to the sender after the timeout
+    pub fn reclaim_expired_tip(ctx: Context<ReclaimExpiredTip>) -> Result<()> {
+        let tip_escrow = &ctx.accounts.tip_escrow;
+        let now = Clock::get()?.unix_timestamp;
+        if now < tip_escrow.expires_at {
+            return err!(ErrorCode::TipNotExpired);
+        }
+
+        // Validate token mint matches sender token account
+        if ctx.accounts.sender_token_account.mint != tip_escrow.token_mint {
+            return err!(ErrorCode::InvalidTokenMint);
+        }
+
+        let amount = ctx.accounts.tip_vault.amount;
+        let sender_key = tip_escrow.sender;
+        let recipient_key = tip_escrow.recipient;
+        let mint_key = tip_escrow.token_mint;
+        let seeds: &[&[u8]] = &[
+            b"tip_escrow",
+            recipient_key.as_ref(),
+            mint_key.as_ref(),
+            sender_key.as_ref(),
+            &[tip_escrow.bump],
+        ];
+        let escrow_info = tip_escrow.to_account_info();
+
+        // Return the vault to the sender, then close it
+        transfer_tokens_signed(
+            &ctx.accounts.token_program,
+            &ctx.accounts.tip_vault,
+            &mut ctx.accounts.sender_token_account,
+            &escrow_info,
+            &ctx.accounts.token_mint,
+            amount,
+            &[seeds],
+        )?;
+        close_token_account(
+            &ctx.accounts.token_program,
+            &ctx.accounts.tip_vault,
+            &ctx.accounts.sender,
+            &escrow_info,
+            &[seeds],
+        )?;
+
+        emit!(TipReclaimed {
+            sender: sender_key,
+            recipient: recipient_key,
+            token_mint: mint_key,
+            amount,
+            timestamp: now,
+        });
+
+        msg!(
+            "Reclaimed {} escrowed tokens ({}) meant for {}",
+            amount,
+            mint_key,
+            recipient_key
+        );
+        Ok(())
+    }
+
+    // Start a crowdfunding campaign; contributions are held in a vault until
+    // the target is met or the deadline passes
+    pub fn create_campaign(
+        ctx: Context<CreateCampaign>,
+        campaign_id: String,
+        target_amount: u64,
+        deadline: i64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        if target_amount == 0 || deadline <= now {
+            return err!(ErrorCode::InvalidCampaign);
+        }
+
+        let campaign = &mut ctx.accounts.campaign;
+        campaign.creator = ctx.accounts.creator.key();
+        campaign.campaign_id = campaign_id.clone();
+        campaign.token_mint = ctx.accounts.token_mint.key();
+        campaign.target_amount = target_amount;
+        campaign.deadline = deadline;
+        campaign.total_raised = 0;
+        campaign.contributor_count = 0;
+        campaign.finalized = false;
+        campaign.bump = ctx.bumps.campaign;
+
+        emit!(CampaignCreated {
+            creator: campaign.creator,
+            campaign_id: campaign_id.clone(),
+            token_mint: campaign.token_mint,
+            target_amount,
+            deadline,
+            timestamp: now,
+        });
+
+        msg!(
+            "Created campaign {} targeting {} ({}) until {}",
+            campaign_id,
+            target_amount,
+            campaign.token_mint,
+            deadline
+        );
+        Ok(())
+    }
+
+    // Contribute to a campaign before its deadline
+    pub fn contribute(ctx: Context<Contribute>, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return err!(ErrorCode::ZeroAmount);
+        }
+        let now = Clock::get()?.unix_timestamp;
+        let campaign = &mut ctx.accounts.campaign;
+        if campaign.finalized || now >= campaign.deadline {
+            return err!(ErrorCode::CampaignClosed);
+        }
+        if ctx.accounts.contributor_token_account.mint != campaign.token_mint {
+            return err!(ErrorCode::InvalidTokenMint);
+        }
+
+        let net_amount = transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.contributor_token_account,
+            &mut ctx.accounts.campaign_vault,
+            &ctx.accounts.contributor,
+            &ctx.accounts.token_mint,
+            amount,
+        )?;
+
+        let contribution = &mut ctx.accounts.contribution;
+        if contribution.contributor == Pubkey::default() {
+            contribution.campaign = campaign.key();
+            contribution.contributor = ctx.accounts.contributor.key();
+            campaign.contributor_count += 1;
+        }
+        contribution.amount = contribution
+            .amount
+            .checked_add(net_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        campaign.total_raised = campaign
+            .total_raised
+            .checked_add(net_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(CampaignContribution {
+            contributor: contribution.contributor,
+            creator: campaign.creator,
+            campaign_id: campaign.campaign_id.clone(),
+            token_mint: campaign.token_mint,
+            amount: net_amount,
+            total_raised: campaign.total_raised,
+            timestamp: now,
+        });
+
+        msg!(
+            "Contributed {} to campaign {} ({} raised)",
+            net_amount,
+            campaign.campaign_id,
+            campaign.total_raised
+        );
+        Ok(())
+    }
+
+    // Release a campaign that met its target to the creator
+    pub fn finalize_campaign(ctx: Context<FinalizeCampaign>) -> Result<()> {
+        let campaign = &ctx.accounts.campaign;
+        if campaign.finalized {
+            return err!(ErrorCode::CampaignClosed);
+        }
+        if campaign.total_raised < campaign.target_amount {
+            return err!(ErrorCode::CampaignTargetNotMet);
+        }
+        if ctx.accounts.creator_token_account.mint != campaign.token_mint
+            || ctx.accounts.fee_token_account.mint != campaign.token_mint
+        {
+            return err!(ErrorCode::InvalidTokenMint);
+        }
+
+        // Split the vault into platform fee and creator share
+        let amount = ctx.accounts.campaign_vault.amount;
+        let fee = calculate_fee(amount, ctx.accounts.platform_config.fee_bps)?;
+        let creator_amount = amount - fee;
+
+        let creator_key = campaign.creator;
+        let seeds: &[&[u8]] = &[
+            b"campaign",
+            creator_key.as_ref(),
+            campaign.campaign_id.as_bytes(),
+            &[campaign.bump],
+        ];
+        let campaign_info = campaign.to_account_info();
+
+        // Release the vault to the creator and platform, then close it
+        let net_amount = transfer_tokens_signed(
+            &ctx.accounts.token_program,
+            &ctx.accounts.campaign_vault,
+            &mut ctx.accounts.creator_token_account,
+            &campaign_info,
+            &ctx.accounts.token_mint,
+            creator_amount,
+            &[seeds],
+        )?;
+        transfer_tokens_signed(
+            &ctx.accounts.token_program,
+            &ctx.accounts.campaign_vault,
+            &mut ctx.accounts.fee_token_account,
+            &campaign_info,
+            &ctx.accounts.token_mint,
+            fee,
+            &[seeds],
+        )?;
+        close_token_account(
+            &ctx.accounts.token_program,
+            &ctx.accounts.campaign_vault,
+            &ctx.accounts.creator,
+            &campaign_info,
+            &[seeds],
+        )?;
+
+        let campaign = &mut ctx.accounts.campaign;
+        campaign.finalized = true;
+
+        emit!(CampaignFinalized {
+            creator: creator_key,
+            campaign_id: campaign.campaign_id.clone(),
+            token_mint: campaign.token_mint,
+            amount,
+            fee,
+            net_amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Finalized campaign {} with {} raised",
+            campaign.campaign_id,
+            amount
+        );
+        Ok(())
+    }
+
+    // Refund a contribution to a campaign that missed its target by the deadline
+    pub fn refund_contribution(ctx: Context<RefundContribution>) -> Result<()> {
+        let campaign = &ctx.accounts.campaign;
+        let now = Clock::get()?.unix_timestamp;
+        if now < campaign.deadline || campaign.total_raised >= campaign.target_amount {
+            return err!(ErrorCode::CampaignNotFailed);
+        }
+        if ctx.accounts.contributor_token_account.mint != campaign.token_mint {
+            return err!(ErrorCode::InvalidTokenMint);
+        }
+
+        let amount = ctx.accounts.contribution.amount;
+        let creator_key = campaign.creator;
+        let seeds: &[&[u8]] = &[
+            b"campaign",
+            creator_key.as_ref(),
+            campaign.campaign_id.as_bytes(),
+            &[campaign.bump],
+        ];
+        let campaign_info = campaign.to_account_info();
+
+        transfer_tokens_signed(
+            &ctx.accounts.token_program,
+            &ctx.accounts.campaign_vault,
+            &mut ctx.accounts.contributor_token_account,
+            &campaign_info,
+            &ctx.accounts.token_mint,
+            amount,
+            &[seeds],
+        )?;
+
+        emit!(ContributionRefunded {
+            contributor: ctx.accounts.contributor.key(),
+            creator: creator_key,
+            campaign_id: campaign.campaign_id.clone(),
+            token_mint: campaign.token_mint,
+            amount,
+            timestamp: now,
+        });
+
+        msg!(
+            "Refunded {} from campaign {} to {}",
+            amount,
+            campaign.campaign_id,
+            ctx.accounts.contributor.key()
+        );
+        Ok(())
+    }
+
+    // Create a paywall for content
+    pub fn create_paywall(
+        ctx: Context<CreatePaywall>,
+        content_id: String,
+        price: u64,
+        token_mint: Pubkey,
+        payees: Vec<SplitShare>,
+        refund_window: i64,
+        pay_what_you_want: bool,
+    ) -> Result<()> {
+        let max_paywall_price = ctx.accounts.platform_config.max_paywall_price;
+        if max_paywall_price > 0 && price > max_paywall_price {
+            return err!(ErrorCode::AmountExceedsLimit);
+        }
+        // No payees means the creator receives the full price
+        if payees.len() > MAX_PAYWALL_PAYEES {
+            return err!(ErrorCode::TooManySplitRecipients);
+        }
+        if !payees.is_empty() {
+            validate_split_shares(&payees)?;
+        }
+        if !(0..=MAX_REFUND_WINDOW).contains(&refund_window) {
+            return err!(ErrorCode::InvalidRefundWindow);
+        }
+
+        let paywall = &mut ctx.accounts.paywall;
+        paywall.creator = ctx.accounts.creator.key();
+        paywall.content_id = content_id.clone();
+        paywall.price = price;
+        paywall.token_mint = token_mint;
+        paywall.access_count = 0;
+        paywall.payees = payees;
+        paywall.refund_window = refund_window;
+        paywall.pending_settlements = 0;
+        paywall.schedule = None;
+        paywall.pay_what_you_want = pay_what_you_want;
+        paywall.mint_prices = Vec::new();
+        paywall.price_feed = None;
+        paywall.access_pass = false;
+        paywall.access_pass_bump = 0;
+        paywall.royalty_bps = 0;
+        paywall.granted_count = 0;
+        paywall.promo_count = 0;
+        paywall.closed = false;
+        msg!(
+            "Created paywall for content {} with price {} ({})",
+            content_id,
+            price,
+            token_mint
+        );
+        Ok(())
+    }
+
+    // Update the price and payment mint of a paywall
+    pub fn update_paywall(
+        ctx: Context<UpdatePaywall>,
+        content_id: String,
+        price: u64,
+        token_mint: Pubkey,
+        pay_what_you_want: bool,
+    ) -> Result<()> {
+        let max_paywall_price = ctx.accounts.platform_config.max_paywall_price;
+        if max_paywall_price > 0 && price > max_paywall_price {
+            return err!(ErrorCode::AmountExceedsLimit);
+        }
+
+        let paywall = &mut ctx.accounts.paywall;
+        if paywall
+            .mint_prices
+            .iter()
+            .any(|p| p.token_mint == token_mint)
+        {
+            return err!(ErrorCode::InvalidTokenMint);
+        }
+        // An early-bird price in US cents means nothing once the price is in
+        // tokens again; otherwise it must stay below the new price
+        if paywall.price_feed.is_some() {
+            paywall.schedule = None;
+        } else if let Some(schedule) = paywall.schedule {
+            schedule.validate(price)?;
+        }
+        paywall.price = price;
+        paywall.token_mint = token_mint;
+        paywall.pay_what_you_want = pay_what_you_want;
+        paywall.price_feed = None;
+
+        emit!(PaywallUpdated {
+            creator: paywall.creator,
+            content_id: content_id.clone(),
+            price,
+            token_mint,
+            pay_what_you_want,
+            price_feed: None,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Updated paywall for content {} to price {} ({})",
+            content_id,
+            price,
+            token_mint
+        );
+        Ok(())
+    }
+
+    // Price a paywall in US cents; unlocks in the primary mint are converted
+    // at the token's price from a Pyth price feed, and refused if the
+    // converted price exceeds max_paywall_price. update_paywall switches
+    // back to a token-denominated price
+    pub fn set_usd_price(
+        ctx: Context<UpdatePaywall>,
+        content_id: String,
+        usd_cents: u64,
+        price_feed: Pubkey,
+    ) -> Result<()> {
+        let paywall = &mut ctx.accounts.paywall;
+        // Same as in update_paywall, with the units the other way around
+        if paywall.price_feed.is_none() {
+            paywall.schedule = None;
+        } else if let Some(schedule) = paywall.schedule {
+            schedule.validate(usd_cents)?;
+        }
+        paywall.price = usd_cents;
+        paywall.price_feed = Some(price_feed);
+
+        emit!(PaywallUpdated {
+            creator: paywall.creator,
+            content_id: content_id.clone(),
+            price: usd_cents,
+            token_mint: paywall.token_mint,
+            pay_what_you_want: paywall.pay_what_you_want,
+            price_feed: Some(price_feed),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Updated paywall for content {} to {} US cents ({})",
+            content_id,
+            usd_cents,
+            price_feed
+        );
+        Ok(())
+    }
+
+    // Replace the prices a paywall accepts in mints other than its primary one
+    pub fn set_mint_prices(
+        ctx: Context<UpdatePaywall>,
+        content_id: String,
+        mint_prices: Vec<MintPrice>,
+    ) -> Result<()> {
+        if mint_prices.len() > MAX_PAYWALL_MINTS {
+            return err!(ErrorCode::TooManyPaywallMints);
+        }
+        let paywall = &mut ctx.accounts.paywall;
+        let max_paywall_price = ctx.accounts.platform_config.max_paywall_price;
+        for (i, entry) in mint_prices.iter().enumerate() {
+            if max_paywall_price > 0 && entry.price > max_paywall_price {
+                return err!(ErrorCode::AmountExceedsLimit);
+            }
+            // Each mint may only be priced once
+            if entry.token_mint == paywall.token_mint
+                || mint_prices[..i]
+                    .iter()
+                    .any(|other| other.token_mint == entry.token_mint)
+            {
+                return err!(ErrorCode::InvalidTokenMint);
+            }
+        }
+        paywall.mint_prices = mint_prices.clone();
+
+        emit!(MintPricesUpdated {
+            creator: paywall.creator,
+            content_id: content_id.clone(),
+            mint_prices,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Updated paywall for content {} to accept {} additional mints",
+            content_id,
+            paywall.mint_prices.len()
+        );
+        Ok(())
+    }
+
+    // Set or clear the time-based pricing schedule of a paywall
+    pub fn set_pricing_schedule(
+        ctx: Context<UpdatePaywall>,
+        content_id: String,
+        schedule: Option<PricingSchedule>,
+    ) -> Result<()> {
+        let paywall = &mut ctx.accounts.paywall;
+        if let Some(schedule) = &schedule {
+            schedule.validate(paywall.price)?;
+        }
+        paywall.schedule = schedule;
+
+        emit!(PricingScheduleUpdated {
+            creator: paywall.creator,
+            content_id: content_id.clone(),
+            schedule,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Updated pricing schedule for content {}", content_id);
+        Ok(())
+    }
+
+    // Close a paywall so it can no longer be unlocked. Unused paywalls are
+    // closed outright, returning their rent to the creator. A re-created
+    // paywall with the same content_id gets the same address, so paywalls
+    // that receipts, promos or an access pass still point at are only
+    // marked closed and keep their account
+    pub fn close_paywall(ctx: Context<ClosePaywall>, content_id: String) -> Result<()> {
+        let paywall = &mut ctx.accounts.paywall;
+        let in_use = paywall.access_count > 0
+            || paywall.granted_count > 0
+            || paywall.promo_count > 0
+            || paywall.access_pass;
+        paywall.closed = true;
+
+        emit!(PaywallClosed {
+            creator: paywall.creator,
+            content_id: content_id.clone(),
+            access_count: paywall.access_count,
+            account_closed: !in_use,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        if !in_use {
+            paywall.close(ctx.accounts.creator.to_account_info())?;
+        }
+
+        msg!("Closed paywall for content {}", content_id);
+        Ok(())
+    }
+
+    // Unlock paywall by paying with the specified token; for paywalls with
+    // payees, their token accounts are passed as remaining accounts in order.
+    // Paywalls with a refund window hold the payment in an escrow vault until
+    // it is settled, and require `unlock_vault`. `amount` is the most the
+    // user agrees to pay; pay-what-you-want paywalls charge all of it, with
+    // anything above the price recorded as a tip to the creator
+    pub fn unlock_paywall<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UnlockPaywall<'info>>,
+        content_id: String,
+        amount: u64,
+    ) -> Result<()> {
+        // Gifts go through gift_unlock
+        if ctx.accounts.beneficiary.is_some() {
+            return err!(ErrorCode::InvalidBeneficiary);
+        }
+
+        let price = pay_what_you_want_price(ctx.accounts, amount)?;
+        process_unlock(
+            ctx.accounts,
+            ctx.remaining_accounts,
+            ctx.bumps.access_receipt,
+            content_id,
+            price,
+        )
+    }
+
+    // Unlock paywall on behalf of `beneficiary`, who receives the access
+    // receipt (and access pass, if any) while the signer pays. Gifts are paid
+    // out immediately and cannot be refunded, so `unlock_vault` is never used
+    pub fn gift_unlock<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UnlockPaywall<'info>>,
+        content_id: String,
+        amount: u64,
+    ) -> Result<()> {
+        if ctx.accounts.beneficiary.is_none() {
+            return err!(ErrorCode::InvalidBeneficiary);
+        }
+
+        let price = pay_what_you_want_price(ctx.accounts, amount)?;
+        process_unlock(
+            ctx.accounts,
+            ctx.remaining_accounts,
+            ctx.bumps.access_receipt,
+            content_id,
+            price,
+        )
+    }
+
+    // Create the access pass mint of a paywall; from then on every unlock
+    // also mints one 0-decimal pass token to the buyer, which can be traded
+    // and checked with verify_access_pass. The pass is a Token-2022 mint
+    // whose on-mint metadata carries the royalty and creator for
+    // marketplaces to honour on secondary sales
+    pub fn enable_access_pass(
+        ctx: Context<EnableAccessPass>,
+        content_id: String,
+        royalty_bps: u16,
+        name: String,
+        symbol: String,
+        uri: String,
+    ) -> Result<()> {
+        if royalty_bps as u64 > BPS_DENOMINATOR {
+            return err!(ErrorCode::InvalidRoyaltyBps);
+        }
+        let paywall = &mut ctx.accounts.paywall;
+        // A refunded unlock could not take back a pass that was already traded
+        if paywall.refund_window > 0 {
+            return err!(ErrorCode::InvalidRefundWindow);
+        }
+        paywall.access_pass = true;
+        paywall.access_pass_bump = ctx.bumps.access_pass_mint;
+        paywall.royalty_bps = royalty_bps;
+
+        // The mint is its own metadata update authority, so the royalty can
+        // only change through this program
+        let mint_info = ctx.accounts.access_pass_mint.to_account_info();
+        let paywall_key = paywall.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"access_pass",
+            paywall_key.as_ref(),
+            &[paywall.access_pass_bump],
+        ]];
+        let metadata = TokenMetadata {
+            update_authority: OptionalNonZeroPubkey(mint_info.key()),
+            mint: mint_info.key(),
+            name: name.clone(),
+            symbol: symbol.clone(),
+            uri: uri.clone(),
+            additional_metadata: vec![
+                (
+                    ROYALTY_BPS_METADATA_KEY.to_string(),
+                    royalty_bps.to_string(),
+                ),
+                (
+                    CREATOR_METADATA_KEY.to_string(),
+                    paywall.creator.to_string(),
+                ),
+            ],
+        };
+        // Token-2022 reallocs the mint for the metadata but leaves the rent to us
+        let space = mint_info.data_len() + metadata.tlv_size_of()?;
+        let rent = Rent::get()?.minimum_balance(space);
+        transfer_lamports(
+            &ctx.accounts.creator,
+            &mint_info,
+            &ctx.accounts.system_program,
+            rent.saturating_sub(mint_info.lamports()),
+        )?;
+        token_metadata_initialize(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TokenMetadataInitialize {
+                    token_program_id: ctx.accounts.token_program.to_account_info(),
+                    metadata: mint_info.clone(),
+                    update_authority: mint_info.clone(),
+                    mint_authority: mint_info.clone(),
+                    mint: mint_info.clone(),
+                },
+                signer_seeds,
+            ),
+            name,
+            symbol,
+            uri,
+        )?;
+        for (key, value) in metadata.additional_metadata {
+            token_metadata_update_field(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    TokenMetadataUpdateField {
+                        token_program_id: ctx.accounts.token_program.to_account_info(),
+                        metadata: mint_info.clone(),
+                        update_authority: mint_info.clone(),
+                    },
+                    signer_seeds,
+                ),
+                Field::Key(key),
+                value,
+            )?;
+        }
+
+        emit!(AccessPassEnabled {
+            creator: paywall.creator,
+            content_id: content_id.clone(),
+            mint: ctx.accounts.access_pass_mint.key(),
+            royalty_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Enabled access passes for content {} ({})",
+            content_id,
+            ctx.accounts.access_pass_mint.key()
+        );
+        Ok(())
+    }
+
+    // Create a promo code giving a percentage off a paywall's price
+    pub fn create_promo(
+        ctx: Context<CreatePromo>,
+        content_id: String,
+        code: String,
+        discount_percent: u8,
+        max_redemptions: u32,
+        expires_at: i64,
+    ) -> Result<()> {
+        if discount_percent == 0 || discount_percent > 100 {
+            return err!(ErrorCode::InvalidPromo);
+        }
+
+        let promo = &mut ctx.accounts.promo;
+        promo.paywall = ctx.accounts.paywall.key();
+        promo.code = code.clone();
+        promo.discount_percent = discount_percent;
+        promo.max_redemptions = max_redemptions;
+        promo.redemptions = 0;
+        promo.expires_at = expires_at;
+        ctx.accounts.paywall.promo_count += 1;
+        msg!(
+            "Created promo {} for content {} with {}% off",
+            code,
+            content_id,
+            discount_percent
+        );
+        Ok(())
+    }
+
+    // Close a promo code and return its rent to the creator
+    pub fn close_promo(ctx: Context<ClosePromo>, content_id: String, code: String) -> Result<()> {
+        let paywall = &mut ctx.accounts.paywall;
+        paywall.promo_count = paywall
+            .promo_count
+            .checked_sub(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        msg!("Closed promo {} for content {}", code, content_id);
+        Ok(())
+    }
+
+    // Unlock paywall at the discounted price of a promo code, optionally as a
+    // gift to `beneficiary`. `max_amount` is the most the user agrees to pay,
+    // bounding the converted price of USD-priced paywalls
+    pub fn unlock_paywall_with_promo<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UnlockPaywallWithPromo<'info>>,
+        content_id: String,
+        code: String,
+        max_amount: u64,
+    ) -> Result<()> {
+        let promo = &mut ctx.accounts.promo;
+        let now = Clock::get()?.unix_timestamp;
+        if promo.expires_at > 0 && now > promo.expires_at {
+            return err!(ErrorCode::PromoExpired);
+        }
+        if promo.max_redemptions > 0 && promo.redemptions >= promo.max_redemptions {
+            return err!(ErrorCode::PromoExhausted);
+        }
+        promo.redemptions += 1;
+
+        let list_price = resolve_unlock_price(&ctx.accounts.unlock, now)?;
+        let discount = (list_price as u128 * promo.discount_percent as u128 / 100) as u64;
+        if list_price - discount > max_amount {
+            return err!(ErrorCode::AmountBelowPrice);
+        }
+        let price = UnlockPrice {
+            amount: list_price - discount,
+            discount,
+            tip: 0,
+            promo: Some(promo.key()),
+        };
+        msg!(
+            "Redeemed promo {} ({} of {} redemptions)",
+            code,
+            promo.redemptions,
+            promo.max_redemptions
+        );
+        process_unlock(
+            &mut ctx.accounts.unlock,
+            ctx.remaining_accounts,
+            ctx.bumps.unlock.access_receipt,
+            content_id,
+            price,
+        )
+    }
+
+    // Grant free access to a list of wallets; their access receipt accounts
+    // are passed as remaining accounts in the same order. Wallets that
+    // already hold access are skipped
+    pub fn grant_access<'info>(
+        ctx: Context<'_, '_, 'info, 'info, GrantAccess<'info>>,
+        content_id: String,
+        users: Vec<Pubkey>,
+    ) -> Result<()> {
+        if users.is_empty()
+            || users.len() > MAX_ACCESS_GRANTS
+            || ctx.remaining_accounts.len() != users.len()
+        {
+            return err!(ErrorCode::InvalidGrant);
+        }
+
+        let paywall = &mut ctx.accounts.paywall;
+        let paywall_key = paywall.key();
+        let creator_key = ctx.accounts.creator.key();
+        let now = Clock::get()?.unix_timestamp;
+        let mut granted = Vec::with_capacity(users.len());
+        for (user_key, receipt_info) in users.iter().zip(ctx.remaining_accounts.iter()) {
+            if *user_key == creator_key {
+                return err!(ErrorCode::InvalidGrant);
+            }
+            let (receipt_key, receipt_bump) = Pubkey::find_program_address(
+                &[b"access_receipt", paywall_key.as_ref(), user_key.as_ref()],
+                ctx.program_id,
+            );
+            if receipt_info.key() != receipt_key {
+                return err!(ErrorCode::InvalidGrant);
+            }
+            if !receipt_info.data_is_empty() {
+                continue;
+            }
+
+            init_access_receipt(
+                receipt_info,
+                &ctx.accounts.creator,
+                &ctx.accounts.system_program,
+                &AccessReceipt {
+                    paywall: paywall_key,
+                    user: *user_key,
+                    amount: 0,
+                    unlocked_at: now,
+                    token_mint: paywall.token_mint,
+                    refundable_until: now,
+                    settled: true,
+                    bump: receipt_bump,
+                    kind: AccessKind::Grant,
+                    payer: creator_key,
+                    tip: 0,
+                    promo: None,
+                    pass_minted: false,
+                },
+            )?;
+            granted.push(*user_key);
+        }
+        paywall.granted_count += granted.len() as u64;
+
+        let granted_count = granted.len();
+        emit!(AccessGranted {
+            creator: creator_key,
+            content_id: content_id.clone(),
+            users: granted,
+            timestamp: now,
+        });
+
+        msg!(
+            "Granted access to content {} to {} wallets",
+            content_id,
+            granted_count
+        );
+        Ok(())
+    }
+
+    // Revoke access granted with grant_access, closing the receipt and
+    // returning its rent to the creator. Paid access cannot be revoked
+    pub fn revoke_access(ctx: Context<RevokeAccess>, content_id: String) -> Result<()> {
+        let paywall = &mut ctx.accounts.paywall;
+        paywall.granted_count = paywall
+            .granted_count
+            .checked_sub(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(AccessRevoked {
+            creator: paywall.creator,
+            content_id: content_id.clone(),
+            user: ctx.accounts.user.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Revoked access to content {} from {}",
+            content_id,
+            ctx.accounts.user.key()
+        );
+        Ok(())
+    }
+
+    // Refund an escrowed unlock within the paywall's refund window; this
+    // closes the access receipt, revoking access
+    pub fn request_refund(ctx: Context<RequestRefund>, content_id: String) -> Result<()> {
+        let access_receipt = &ctx.accounts.access_receipt;
+        if access_receipt.settled {
+            return err!(ErrorCode::UnlockAlreadySettled);
+        }
+        let now = Clock::get()?.unix_timestamp;
+        if now > access_receipt.refundable_until {
+            return err!(ErrorCode::RefundWindowClosed);
+        }
+        if ctx.accounts.user_token_account.mint != access_receipt.token_mint {
+            return err!(ErrorCode::InvalidTokenMint);
+        }
+
+        let paywall_key = access_receipt.paywall;
+        let user_key = access_receipt.user;
+        let seeds: &[&[u8]] = &[
+            b"access_receipt",
+            paywall_key.as_ref(),
+            user_key.as_ref(),
+            &[access_receipt.bump],
+        ];
+        let receipt_info = access_receipt.to_account_info();
+
+        // Return the vault to the buyer, then close it
+        let amount_paid = access_receipt.amount;
+        let tip = access_receipt.tip;
+        let promo_key = access_receipt.promo;
+        let amount = ctx.accounts.unlock_vault.amount;
+        transfer_tokens_signed(
+            &ctx.accounts.token_program,
+            &ctx.accounts.unlock_vault,
+            &mut ctx.accounts.user_token_account,
+            &receipt_info,
+            &ctx.accounts.token_mint,
+            amount,
+            &[seeds],
+        )?;
+        close_token_account(
+            &ctx.accounts.token_program,
+            &ctx.accounts.unlock_vault,
+            &ctx.accounts.user,
+            &receipt_info,
+            &[seeds],
+        )?;
+
+        let paywall = &mut ctx.accounts.paywall;
+        paywall.access_count -= 1;
+        paywall.pending_settlements -= 1;
+        ctx.accounts.user_stats.revert_sent(amount_paid)?;
+        ctx.accounts.creator_stats.revert_received(amount_paid)?;
+
+        // Undo the tip credited to the creator and give the promo its
+        // redemption back
+        if tip > 0 {
+            let creator_profile = ctx
+                .accounts
+                .creator_profile
+                .as_mut()
+                .ok_or(ErrorCode::CreatorProfileRequired)?;
+            creator_profile.interaction_count = creator_profile
+                .interaction_count
+                .checked_sub(1)
+                .ok_or(ErrorCode::MathOverflow)?;
+        }
+        if let Some(promo_key) = promo_key {
+            let promo_info = ctx
+                .accounts
+                .promo
+                .as_ref()
+                .filter(|info| info.key() == promo_key)
+                .ok_or(ErrorCode::InvalidPromo)?;
+            // A promo closed since the unlock has nothing to give back
+            if promo_info.owner == &crate::ID {
+                let mut data = promo_info.try_borrow_mut_data()?;
+                let mut promo = Promo::try_deserialize(&mut &data[..])?;
+                promo.redemptions = promo.redemptions.saturating_sub(1);
+                promo.try_serialize(&mut &mut data[..])?;
+            }
+        }
+
+        emit!(UnlockRefunded {
+            user: user_key,
+            creator: paywall.creator,
+            content_id: content_id.clone(),
+            token_mint: access_receipt.token_mint,
+            amount,
+            timestamp: now,
+        });
+
+        msg!("Refunded unlock of content {} to {}", content_id, user_key);
+        Ok(())
+    }
+
+    // Release an escrowed unlock to the creator, payees and platform once the
+    // refund window has passed; anyone can call this
+    pub fn settle_unlock<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettleUnlock<'info>>,
+        content_id: String,
+    ) -> Result<()> {
+        let access_receipt = &ctx.accounts.access_receipt;
+        if access_receipt.settled {
+            return err!(ErrorCode::UnlockAlreadySettled);
+        }
+        let now = Clock::get()?.unix_timestamp;
+        if now <= access_receipt.refundable_until {
+            return err!(ErrorCode::RefundWindowOpen);
+        }
+        if ctx.accounts.token_mint.key() != access_receipt.token_mint
+            || ctx.accounts.creator_token_account.mint != access_receipt.token_mint
+            || ctx.accounts.fee_token_account.mint != access_receipt.token_mint
+        {
+            return err!(ErrorCode::InvalidTokenMint);
+        }
+
+        let paywall_key = access_receipt.paywall;
+        let user_key = access_receipt.user;
+        let seeds: &[&[u8]] = &[
+            b"access_receipt",
+            paywall_key.as_ref(),
+            user_key.as_ref(),
+            &[access_receipt.bump],
+        ];
+        let receipt_info = access_receipt.to_account_info();
+
+        // Pay out the vault, then close it and return its rent to the buyer
+        let amount = ctx.accounts.unlock_vault.amount;
+        let source = PaymentSource {
+            token_program: &ctx.accounts.token_program,
+            from: &ctx.accounts.unlock_vault,
+            authority: &receipt_info,
+            mint: &ctx.accounts.token_mint,
+            signer_seeds: &[seeds],
+        };
+        let (fee, net_amount, payments) = distribute_paywall_payment(
+            &source,
+            &ctx.accounts.paywall.payees,
+            ctx.remaining_accounts,
+            &mut ctx.accounts.creator_token_account,
+            &mut ctx.accounts.fee_token_account,
+            amount,
+            ctx.accounts.platform_config.fee_bps,
+        )?;
+        close_token_account(
+            &ctx.accounts.token_program,
+            &ctx.accounts.unlock_vault,
+            &ctx.accounts.user,
+            &receipt_info,
+            &[seeds],
+        )?;
+
+        ctx.accounts.access_receipt.settled = true;
+        let paywall = &mut ctx.accounts.paywall;
+        paywall.pending_settlements -= 1;
+
+        emit!(UnlockSettled {
+            user: user_key,
+            creator: paywall.creator,
+            content_id: content_id.clone(),
+            token_mint: ctx.accounts.access_receipt.token_mint,
+            amount,
+            fee,
+            net_amount,
+            payments,
+            timestamp: now,
+        });
+
+        msg!("Settled unlock of content {} by {}", content_id, user_key);
+        Ok(())
+    }
+
+    // Create a bundle of paywalls owned by the creator, sold at its own price.
+    // The paywalls are passed as remaining accounts in the same order
+    pub fn create_bundle<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreateBundle<'info>>,
+        bundle_id: String,
+        price: u64,
+        token_mint: Pubkey,
+        paywalls: Vec<Pubkey>,
+    ) -> Result<()> {
+        let max_paywall_price = ctx.accounts.platform_config.max_paywall_price;
+        if max_paywall_price > 0 && price > max_paywall_price {
+            return err!(ErrorCode::AmountExceedsLimit);
+        }
+        if paywalls.is_empty() || paywalls.len() > MAX_BUNDLE_PAYWALLS {
+            return err!(ErrorCode::InvalidBundle);
+        }
+        if ctx.remaining_accounts.len() != paywalls.len() {
+            return err!(ErrorCode::InvalidBundle);
+        }
+        for (i, (paywall_key, account_info)) in
+            paywalls.iter().zip(ctx.remaining_accounts).enumerate()
+        {
+            let paywall = Account::<Paywall>::try_from(account_info)?;
+            // Bundle revenue goes to the creator alone, so paywalls shared
+            // with payees cannot be bundled
+            if paywall.key() != *paywall_key
+                || paywall.creator != ctx.accounts.creator.key()
+                || !paywall.payees.is_empty()
+                || paywall.closed
+                || paywalls[..i].contains(paywall_key)
+            {
+                return err!(ErrorCode::InvalidBundle);
+            }
+        }
+
+        let bundle = &mut ctx.accounts.bundle;
+        bundle.creator = ctx.accounts.creator.key();
+        bundle.bundle_id = bundle_id.clone();
+        bundle.price = price;
+        bundle.token_mint = token_mint;
+        bundle.paywalls = paywalls;
+        bundle.access_count = 0;
+        msg!(
+            "Created bundle {} of {} paywalls with price {} ({})",
+            bundle_id,
+            bundle.paywalls.len(),
+            price,
+            token_mint
+        );
+        Ok(())
+    }
+
+    // Unlock every paywall in a bundle with one payment. Remaining accounts
+    // are (paywall, access receipt) pairs in bundle order; paywalls the user
+    // already has access to are skipped. Bundle payments settle immediately,
+    // regardless of the paywalls' refund windows
+    pub fn unlock_bundle<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UnlockBundle<'info>>,
+        bundle_id: String,
+    ) -> Result<()> {
+        let bundle = &mut ctx.accounts.bundle;
+        let amount = bundle.price;
+
+        // Validate token mint matches bundle and token accounts
+        if bundle.token_mint != ctx.accounts.token_mint.key()
+            || ctx.accounts.user_token_account.mint != ctx.accounts.token_mint.key()
+            || ctx.accounts.creator_token_account.mint != ctx.accounts.token_mint.key()
+            || ctx.accounts.fee_token_account.mint != ctx.accounts.token_mint.key()
+        {
+            return err!(ErrorCode::InvalidTokenMint);
+        }
+        if ctx.remaining_accounts.len() != bundle.paywalls.len() * 2 {
+            return err!(ErrorCode::InvalidBundle);
+        }
+
+        // Split the price into platform fee and creator share
+        let fee = calculate_fee(amount, ctx.accounts.platform_config.fee_bps)?;
+        let creator_amount = amount - fee;
+
+        // Transfer tokens to creator and platform
+        let net_amount = transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.user_token_account,
+            &mut ctx.accounts.creator_token_account,
+            &ctx.accounts.user,
+            &ctx.accounts.token_mint,
+            creator_amount,
+        )?;
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.user_token_account,
+            &mut ctx.accounts.fee_token_account,
+            &ctx.accounts.user,
+            &ctx.accounts.token_mint,
+            fee,
+        )?;
+
+        // Issue an access receipt for each paywall
+        let now = Clock::get()?.unix_timestamp;
+        let user_key = ctx.accounts.user.key();
+        let mut unlocked = Vec::with_capacity(bundle.paywalls.len());
+        for (paywall_key, accounts) in bundle.paywalls.iter().zip(ctx.remaining_accounts.chunks(2))
+        {
+            if accounts[0].key() != *paywall_key {
+                return err!(ErrorCode::InvalidBundle);
+            }
+            // Paywalls closed since the bundle was created are skipped, whether
+            // their account is gone or only marked closed
+            if accounts[0].owner != ctx.program_id {
+                continue;
+            }
+            let mut paywall = Account::<Paywall>::try_from(&accounts[0])?;
+            if paywall.closed {
+                continue;
+            }
+            let receipt_info = &accounts[1];
+            let (receipt_key, receipt_bump) = Pubkey::find_program_address(
+                &[b"access_receipt", paywall_key.as_ref(), user_key.as_ref()],
+                ctx.program_id,
+            );
+            if receipt_info.key() != receipt_key {
+                return err!(ErrorCode::InvalidBundle);
+            }
+            if !receipt_info.data_is_empty() {
+                continue;
+            }
+
+            init_access_receipt(
+                receipt_info,
+                &ctx.accounts.user,
+                &ctx.accounts.system_program,
+                &AccessReceipt {
+                    paywall: *paywall_key,
+                    user: user_key,
+                    amount: 0,
+                    unlocked_at: now,
+                    token_mint: bundle.token_mint,
+                    refundable_until: now,
+                    settled: true,
+                    bump: receipt_bump,
+                    kind: AccessKind::Bundle,
+                    payer: user_key,
+                    tip: 0,
+                    promo: None,
+                    pass_minted: false,
+                },
+            )?;
+            paywall.access_count += 1;
+            paywall.exit(ctx.program_id)?;
+            unlocked.push(*paywall_key);
+        }
+        bundle.access_count += 1;
+
+        emit!(BundleUnlockEvent {
+            user: user_key,
+            creator: bundle.creator,
+            bundle_id: bundle_id.clone(),
+            token_mint: bundle.token_mint,
+            amount,
+            fee,
+            net_amount,
+            paywalls: unlocked,
+            timestamp: now,
+        });
+
+        msg!("Unlocked bundle {} by {}", bundle_id, user_key);
+        Ok(())
+    }
+
+    // Create a subscription plan for a creator
+    pub fn create_subscription_plan(
+        ctx: Context<CreateSubscriptionPlan>,
+        token_mint: Pubkey,
+        price_per_period: u64,
+        period_seconds: i64,
+    ) -> Result<()> {
+        if period_seconds <= 0 {
+            return err!(ErrorCode::InvalidPeriod);
+        }
+        let max_paywall_price = ctx.accounts.platform_config.max_paywall_price;
+        if max_paywall_price > 0 && price_per_period > max_paywall_price {
+            return err!(ErrorCode::AmountExceedsLimit);
+        }
+
+        let plan = &mut ctx.accounts.subscription_plan;
+        plan.creator = ctx.accounts.creator.key();
+        plan.token_mint = token_mint;
+        plan.price_per_period = price_per_period;
+        plan.period_seconds = period_seconds;
+        plan.subscriber_count = 0;
+        msg!(
+            "Created subscription plan for {} at {} ({}) every {} seconds",
+            plan.creator,
+            price_per_period,
+            token_mint,
+            period_seconds
+        );
+        Ok(())
+    }
+
+    // Subscribe to a creator, or extend an existing subscription, for a number of periods
+    pub fn subscribe(ctx: Context<Subscribe>, periods: u32) -> Result<()> {
+        if periods == 0 {
+            return err!(ErrorCode::InvalidPeriod);
+        }
+
+        let plan = &mut ctx.accounts.subscription_plan;
+        let amount = plan
+            .price_per_period
+            .checked_mul(periods as u64)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        // Validate token mint matches plan and token accounts
+        if plan.token_mint != ctx.accounts.token_mint.key()
+            || ctx.accounts.subscriber_token_account.mint != ctx.accounts.token_mint.key()
+            || ctx.accounts.creator_token_account.mint != ctx.accounts.token_mint.key()
+            || ctx.accounts.fee_token_account.mint != ctx.accounts.token_mint.key()
+        {
+            return err!(ErrorCode::InvalidTokenMint);
+        }
+
+        // Split the price into platform fee and creator share
+        let fee = calculate_fee(amount, ctx.accounts.platform_config.fee_bps)?;
+        let creator_amount = amount - fee;
+
+        // Transfer tokens to creator and platform
+        let net_amount = transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.subscriber_token_account,
+            &mut ctx.accounts.creator_token_account,
+            &ctx.accounts.subscriber,
+            &ctx.accounts.token_mint,
+            creator_amount,
+        )?;
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.subscriber_token_account,
+            &mut ctx.accounts.fee_token_account,
+            &ctx.accounts.subscriber,
+            &ctx.accounts.token_mint,
+            fee,
+        )?;
+
+        // Extend from the current expiry, or from now if new or lapsed
+        let now = Clock::get()?.unix_timestamp;
+        let subscription = &mut ctx.accounts.subscription;
+        if subscription.subscriber == Pubkey::default() {
+            subscription.plan = plan.key();
+            subscription.subscriber = ctx.accounts.subscriber.key();
+            subscription.started_at = now;
+            plan.subscriber_count += 1;
+        }
+        let duration = plan
+            .period_seconds
+            .checked_mul(periods as i64)
+            .ok_or(ErrorCode::MathOverflow)?;
+        subscription.expires_at = subscription
+            .expires_at
+            .max(now)
+            .checked_add(duration)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(SubscriptionEvent {
+            subscriber: subscription.subscriber,
+            creator: plan.creator,
+            token_mint: plan.token_mint,
+            amount,
+            fee,
+            net_amount,
+            periods,
+            expires_at: subscription.expires_at,
+            timestamp: now,
+        });
+
+        msg!(
+            "Subscription of {} to {} extended until {}",
+            subscription.subscriber,
+            plan.creator,
+            subscription.expires_at
+        );
+        Ok(())
+    }
+
+    // Check that a user holds an access receipt for a paywall. Receipts of
+    // unlocks that minted an access pass do not count, as the pass may have
+    // been sold; those are checked with verify_access_pass
+    pub fn verify_access(ctx: Context<VerifyAccess>, content_id: String) -> Result<()> {
+        let access_receipt = &ctx.accounts.access_receipt;
+        if access_receipt.paywall != ctx.accounts.paywall.key()
+            || access_receipt.user != ctx.accounts.user.key()
+        {
+            return err!(ErrorCode::AccessNotFound);
+        }
+        if access_receipt.pass_minted {
+            return err!(ErrorCode::AccessHeldByPass);
+        }
+
+        msg!(
+            "Verified access to content {} for {} (unlocked at {})",
+            content_id,
+            access_receipt.user,
+            access_receipt.unlocked_at
+        );
+        Ok(())
+    }
+
+    // Verify access by the holder's balance of the paywall's access pass
+    pub fn verify_access_pass(ctx: Context<VerifyAccessPass>, content_id: String) -> Result<()> {
+        if ctx.accounts.holder_pass_account.amount == 0 {
+            return err!(ErrorCode::AccessNotFound);
+        }
+
+        msg!(
+            "Verified access pass to content {} for {}",
+            content_id,
+            ctx.accounts.holder.key()
+        );
+        Ok(())
+    }
+}
+
+// Account structures
+#[derive(Accounts)]
+pub struct InitializeUser<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProfile<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_profile", owner.key().as_ref()],
+        bump,
+        has_one = owner,
+        // Profiles created before the profile details were added are smaller
+        realloc = 8 + UserProfile::INIT_SPACE,
+        realloc::payer = owner,
+        realloc::zero = false
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + PlatformConfig::INIT_SPACE,
+        seeds = [b"platform_config"],
+        bump
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    // Only the program's upgrade authority can set up the config, so it
+    // cannot be claimed by front-running the deployment
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key())
+            @ ErrorCode::Unauthorized
+    )]
+    pub program: Program<'info, crate::program::NoiceSolana>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key())
+            @ ErrorCode::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"platform_config"],
+        bump,
+        has_one = admin
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [b"platform_config"],
+        bump,
+        has_one = pending_admin @ ErrorCode::Unauthorized
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    pub pending_admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Tip<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_profile", recipient.key().as_ref()],
+        bump
+    )]
+    pub recipient_profile: Account<'info, UserProfile>,
+    #[account(
+        seeds = [b"platform_config"],
+        bump,
+        constraint = !platform_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub sender_token_account: InterfaceAccount<'info, TokenAccount>,
+    // Must belong to the recipient, or tips could be paid back to the sender
+    // while still counting towards stats and the leaderboard
+    #[account(
+        mut,
+        constraint = recipient_token_account.owner == recipient.key()
+            @ ErrorCode::InvalidTokenOwner
+    )]
+    pub recipient_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = fee_token_account.owner == platform_config.fee_recipient
+            @ ErrorCode::InvalidFeeRecipient
+    )]
+    pub fee_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = 8 + MintStats::INIT_SPACE,
+        seeds = [b"mint_stats", sender.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub sender_stats: Account<'info, MintStats>,
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = 8 + MintStats::INIT_SPACE,
+        seeds = [b"mint_stats", recipient.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub recipient_stats: Account<'info, MintStats>,
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = 8 + SupporterTotal::INIT_SPACE,
+        seeds = [
+            b"supporter_total",
+            recipient.key().as_ref(),
+            sender.key().as_ref(),
+            token_mint.key().as_ref()
+        ],
+        bump
+    )]
+    pub supporter_total: Account<'info, SupporterTotal>,
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = 8 + Leaderboard::INIT_SPACE,
+        seeds = [b"leaderboard", recipient.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    /// CHECK: Only used as a seed and checked against the token account owner.
+    /// Self-tips are refused, as the sender and recipient stats would alias
+    #[account(constraint = recipient.key() != sender.key() @ ErrorCode::SelfPayment)]
+    pub recipient: AccountInfo<'info>,
+    pub token_mint: InterfaceAccount<'info, Mint>, // Token mint for the SPL or Token-2022 token
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TipSol<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_profile", recipient.key().as_ref()],
+        bump
+    )]
+    pub recipient_profile: Account<'info, UserProfile>,
+    #[account(
+        seeds = [b"platform_config"],
+        bump,
+        constraint = !platform_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    /// CHECK: Receives lamports; bound to the profile through its seeds
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    /// CHECK: Receives the platform fee; must match the config
+    #[account(
+        mut,
+        address = platform_config.fee_recipient @ ErrorCode::InvalidFeeRecipient
+    )]
+    pub fee_recipient: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(split_id: String)]
+pub struct CreateRevenueSplit<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + RevenueSplit::INIT_SPACE,
+        seeds = [b"revenue_split", authority.key().as_ref(), split_id.as_bytes()],
+        bump
+    )]
+    pub revenue_split: Account<'info, RevenueSplit>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TipSplit<'info> {
+    #[account(
+        seeds = [
+            b"revenue_split",
+            revenue_split.authority.as_ref(),
+            revenue_split.split_id.as_bytes()
+        ],
+        bump
+    )]
+    pub revenue_split: Account<'info, RevenueSplit>,
+    #[account(
+        seeds = [b"platform_config"],
+        bump,
+        constraint = !platform_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub sender_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = fee_token_account.owner == platform_config.fee_recipient
+            @ ErrorCode::InvalidFeeRecipient
+    )]
+    pub fee_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CreatePledge<'info> {
+    #[account(
+        init,
+        payer = sender,
+        space = 8 + Pledge::INIT_SPACE,
+        seeds = [
+            b"pledge",
+            sender.key().as_ref(),
+            recipient.key().as_ref(),
+            token_mint.key().as_ref()
+        ],
+        bump
+    )]
+    pub pledge: Account<'info, Pledge>,
+    #[account(
+        seeds = [b"platform_config"],
+        bump,
+        constraint = !platform_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(
+        mut,
+        constraint = sender_token_account.delegate.is_none() @ ErrorCode::DelegateInUse
+    )]
+    pub sender_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    /// CHECK: Only used as a seed; checked against the profile when executed
+    #[account(constraint = recipient.key() != sender.key() @ ErrorCode::SelfPayment)]
+    pub recipient: AccountInfo<'info>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecutePledge<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"pledge",
+            pledge.sender.as_ref(),
+            pledge.recipient.as_ref(),
+            pledge.token_mint.as_ref()
+        ],
+        bump = pledge.bump
+    )]
+    pub pledge: Account<'info, Pledge>,
+    #[account(
+        mut,
+        seeds = [b"user_profile", pledge.recipient.as_ref()],
+        bump
+    )]
+    pub recipient_profile: Account<'info, UserProfile>,
+    #[account(
+        seeds = [b"platform_config"],
+        bump,
+        constraint = !platform_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut, address = pledge.sender_token_account)]
+    pub sender_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = recipient_token_account.owner == pledge.recipient
+            @ ErrorCode::InvalidTokenOwner
+    )]
+    pub recipient_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = fee_token_account.owner == platform_config.fee_recipient
+            @ ErrorCode::InvalidFeeRecipient
+    )]
+    pub fee_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = pledge.token_mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CancelPledge<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"pledge",
+            sender.key().as_ref(),
+            pledge.recipient.as_ref(),
+            pledge.token_mint.as_ref()
+        ],
+        bump = pledge.bump,
+        has_one = sender,
+        close = sender
+    )]
+    pub pledge: Account<'info, Pledge>,
+    #[account(mut, address = pledge.sender_token_account)]
+    pub sender_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct EscrowTip<'info> {
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = 8 + TipEscrow::INIT_SPACE,
+        seeds = [
+            b"tip_escrow",
+            recipient.key().as_ref(),
+            token_mint.key().as_ref(),
+            sender.key().as_ref()
+        ],
+        bump
+    )]
+    pub tip_escrow: Account<'info, TipEscrow>,
+    #[account(
+        init_if_needed,
+        payer = sender,
+        seeds = [b"tip_vault", tip_escrow.key().as_ref()],
+        bump,
+        token::mint = token_mint,
+        token::authority = tip_escrow,
+        token::token_program = token_program
+    )]
+    pub tip_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds = [b"platform_config"],
+        bump,
+        constraint = !platform_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub sender_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    /// CHECK: Only used as a seed; the recipient signs when claiming
+    pub recipient: AccountInfo<'info>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimTips<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"tip_escrow",
+            recipient.key().as_ref(),
+            token_mint.key().as_ref(),
+            sender.key().as_ref()
+        ],
+        bump = tip_escrow.bump,
+        has_one = sender,
+        has_one = recipient,
+        close = sender
+    )]
+    pub tip_escrow: Account<'info, TipEscrow>,
+    #[account(
+        mut,
+        seeds = [b"tip_vault", tip_escrow.key().as_ref()],
+        bump
+    )]
+    pub tip_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"platform_config"], bump)]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub recipient_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = fee_token_account.owner == platform_config.fee_recipient
+            @ ErrorCode::InvalidFeeRecipient
+    )]
+    pub fee_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub recipient: Signer<'info>,
+    /// CHECK: Receives the escrow rent; must match the escrow sender
+    #[account(mut)]
+    pub sender: AccountInfo<'info>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimExpiredTip<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"tip_escrow",
+            recipient.key().as_ref(),
+            token_mint.key().as_ref(),
+            sender.key().as_ref()
+        ],
+        bump = tip_escrow.bump,
+        has_one = sender,
+        has_one = recipient,
+        close = sender
+    )]
+    pub tip_escrow: Account<'info, TipEscrow>,
+    #[account(
+        mut,
+        seeds = [b"tip_vault", tip_escrow.key().as_ref()],
+        bump
+    )]
+    pub tip_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub sender_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    /// CHECK: Only used as a seed
+    pub recipient: AccountInfo<'info>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(campaign_id: String)]
+pub struct CreateCampaign<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Campaign::INIT_SPACE,
+        seeds = [b"campaign", creator.key().as_ref(), campaign_id.as_bytes()],
+        bump
+    )]
+    pub campaign: Account<'info, Campaign>,
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"campaign_vault", campaign.key().as_ref()],
+        bump,
+        token::mint = token_mint,
+        token::authority = campaign,
+        token::token_program = token_program
+    )]
+    pub campaign_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds = [b"platform_config"],
+        bump,
+        constraint = !platform_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Contribute<'info> {
+    #[account(
+        mut,
+        seeds = [b"campaign", campaign.creator.as_ref(), campaign.campaign_id.as_bytes()],
+        bump = campaign.bump
+    )]
+    pub campaign: Account<'info, Campaign>,
+    #[account(
+        mut,
+        seeds = [b"campaign_vault", campaign.key().as_ref()],
+        bump
+    )]
+    pub campaign_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = contributor,
+        space = 8 + Contribution::INIT_SPACE,
+        seeds = [b"contribution", campaign.key().as_ref(), contributor.key().as_ref()],
+        bump
+    )]
+    pub contribution: Account<'info, Contribution>,
+    #[account(
+        seeds = [b"platform_config"],
+        bump,
+        constraint = !platform_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub contributor_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub contributor: Signer<'info>,
+    #[account(address = campaign.token_mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeCampaign<'info> {
+    #[account(
+        mut,
+        seeds = [b"campaign", creator.key().as_ref(), campaign.campaign_id.as_bytes()],
+        bump = campaign.bump,
+        has_one = creator
+    )]
+    pub campaign: Account<'info, Campaign>,
+    #[account(
+        mut,
+        seeds = [b"campaign_vault", campaign.key().as_ref()],
+        bump
+    )]
+    pub campaign_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"platform_config"], bump)]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == campaign.creator
+            @ ErrorCode::InvalidTokenOwner
+    )]
+    pub creator_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = fee_token_account.owner == platform_config.fee_recipient
+            @ ErrorCode::InvalidFeeRecipient
+    )]
+    pub fee_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    #[account(address = campaign.token_mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct RefundContribution<'info> {
+    #[account(
+        seeds = [b"campaign", campaign.creator.as_ref(), campaign.campaign_id.as_bytes()],
+        bump = campaign.bump
+    )]
+    pub campaign: Account<'info, Campaign>,
+    #[account(
+        mut,
+        seeds = [b"campaign_vault", campaign.key().as_ref()],
+        bump
+    )]
+    pub campaign_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"contribution", campaign.key().as_ref(), contributor.key().as_ref()],
+        bump,
+        has_one = contributor,
+        close = contributor
+    )]
+    pub contribution: Account<'info, Contribution>,
+    #[account(mut)]
+    pub contributor_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub contributor: Signer<'info>,
+    #[account(address = campaign.token_mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(content_id: String)]
+pub struct CreatePaywall<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Paywall::INIT_SPACE,
+        seeds = [b"paywall", creator.key().as_ref(), content_id.as_bytes()],
+        bump
+    )]
+    pub paywall: Account<'info, Paywall>,
+    #[account(
+        seeds = [b"platform_config"],
+        bump,
+        constraint = !platform_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(content_id: String)]
+pub struct UpdatePaywall<'info> {
+    #[account(
+        mut,
+        seeds = [b"paywall", creator.key().as_ref(), content_id.as_bytes()],
+        bump,
+        has_one = creator
+    )]
+    pub paywall: Account<'info, Paywall>,
+    #[account(
+        seeds = [b"platform_config"],
+        bump,
+        constraint = !platform_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(content_id: String)]
+pub struct ClosePaywall<'info> {
+    #[account(
+        mut,
+        seeds = [b"paywall", creator.key().as_ref(), content_id.as_bytes()],
+        bump,
+        has_one = creator,
+        constraint = paywall.pending_settlements == 0 @ ErrorCode::PendingSettlements,
+        constraint = !paywall.closed @ ErrorCode::PaywallClosed
+    )]
+    pub paywall: Account<'info, Paywall>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(content_id: String)]
+pub struct UnlockPaywall<'info> {
+    #[account(
+        mut,
+        seeds = [b"paywall", paywall.creator.as_ref(), content_id.as_bytes()],
+        bump,
+        constraint = !paywall.closed @ ErrorCode::PaywallClosed
+    )]
+    pub paywall: Account<'info, Paywall>,
+    // Fails with "already in use" if this user has unlocked the paywall before
+    #[account(
+        init,
+        payer = user,
+        space = 8 + AccessReceipt::INIT_SPACE,
+        seeds = [
+            b"access_receipt",
+            paywall.key().as_ref(),
+            beneficiary.as_ref().map_or(user.key(), |b| b.key()).as_ref()
+        ],
+        bump
+    )]
+    pub access_receipt: Account<'info, AccessReceipt>,
+    // Escrow for the payment, only for paywalls with a refund window
+    #[account(
+        init,
+        payer = user,
+        seeds = [b"unlock_vault", access_receipt.key().as_ref()],
+        bump,
+        token::mint = token_mint,
+        token::authority = access_receipt,
+        token::token_program = token_program
+    )]
+    pub unlock_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        seeds = [b"platform_config"],
+        bump,
+        constraint = !platform_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == paywall.creator
+            @ ErrorCode::InvalidTokenOwner
+    )]
+    pub creator_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = fee_token_account.owner == platform_config.fee_recipient
+            @ ErrorCode::InvalidFeeRecipient
+    )]
+    pub fee_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + MintStats::INIT_SPACE,
+        seeds = [b"mint_stats", user.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub user_stats: Account<'info, MintStats>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + MintStats::INIT_SPACE,
+        seeds = [b"mint_stats", paywall.creator.as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub creator_stats: Account<'info, MintStats>,
+    // Credited with the tip of a pay-what-you-want unlock, required if there is one
+    #[account(
+        mut,
+        seeds = [b"user_profile", paywall.creator.as_ref()],
+        bump
+    )]
+    pub creator_profile: Option<Account<'info, UserProfile>>,
+    /// CHECK: Pyth price feed for USD-priced paywalls; checked against the
+    /// paywall and parsed in read_price_feed
+    pub price_feed: Option<AccountInfo<'info>>,
+    // Access pass mint and the user's token account for it, only for
+    // paywalls issuing access passes
+    #[account(
+        mut,
+        seeds = [b"access_pass", paywall.key().as_ref()],
+        bump
+    )]
+    pub access_pass_mint: Option<InterfaceAccount<'info, Mint>>,
+    // Owned by the beneficiary for gifts
+    #[account(mut)]
+    pub user_pass_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    // Token program of the access pass mint, which need not match the
+    // payment token's
+    pub pass_token_program: Option<Interface<'info, TokenInterface>>,
+    /// CHECK: Recipient of a gift unlock; only used as a seed and recorded
+    pub beneficiary: Option<AccountInfo<'info>>,
+    // The creator cannot unlock their own paywall, as the user and creator
+    // stats would alias
+    #[account(
+        mut,
+        constraint = user.key() != paywall.creator @ ErrorCode::SelfPayment
+    )]
+    pub user: Signer<'info>,
+    pub token_mint: InterfaceAccount<'info, Mint>, // Token mint for the SPL or Token-2022 token
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateSubscriptionPlan<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + SubscriptionPlan::INIT_SPACE,
+        seeds = [b"subscription_plan", creator.key().as_ref()],
+        bump
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    #[account(
+        seeds = [b"platform_config"],
+        bump,
+        constraint = !platform_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Subscribe<'info> {
+    #[account(
+        mut,
+        seeds = [b"subscription_plan", subscription_plan.creator.as_ref()],
+        bump
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    #[account(
+        init_if_needed,
+        payer = subscriber,
+        space = 8 + Subscription::INIT_SPACE,
+        seeds = [b"subscription", subscription_plan.key().as_ref(), subscriber.key().as_ref()],
+        bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    #[account(
+        seeds = [b"platform_config"],
+        bump,
+        constraint = !platform_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub subscriber_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == subscription_plan.creator
+            @ ErrorCode::InvalidTokenOwner
+    )]
+    pub creator_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = fee_token_account.owner == platform_config.fee_recipient
+            @ ErrorCode::InvalidFeeRecipient
+    )]
+    pub fee_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(content_id: String)]
+pub struct EnableAccessPass<'info> {
+    #[account(
+        mut,
+        seeds = [b"paywall", creator.key().as_ref(), content_id.as_bytes()],
+        bump,
+        has_one = creator,
+        constraint = !paywall.closed @ ErrorCode::PaywallClosed
+    )]
+    pub paywall: Account<'info, Paywall>,
+    // The mint is its own authority, signing with its seeds
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"access_pass", paywall.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = access_pass_mint,
+        mint::token_program = token_program,
+        extensions::metadata_pointer::authority = access_pass_mint,
+        extensions::metadata_pointer::metadata_address = access_pass_mint
+    )]
+    pub access_pass_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    // Pass mints always use Token-2022 for their metadata extension
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(content_id: String, code: String)]
+pub struct CreatePromo<'info> {
+    #[account(
+        mut,
+        seeds = [b"paywall", creator.key().as_ref(), content_id.as_bytes()],
+        bump,
+        has_one = creator,
+        constraint = !paywall.closed @ ErrorCode::PaywallClosed
+    )]
+    pub paywall: Account<'info, Paywall>,
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Promo::INIT_SPACE,
+        seeds = [b"promo", paywall.key().as_ref(), code.as_bytes()],
+        bump
+    )]
+    pub promo: Account<'info, Promo>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(content_id: String, code: String)]
+pub struct ClosePromo<'info> {
+    #[account(
+        mut,
+        seeds = [b"paywall", creator.key().as_ref(), content_id.as_bytes()],
+        bump,
+        has_one = creator
+    )]
+    pub paywall: Account<'info, Paywall>,
+    #[account(
+        mut,
+        seeds = [b"promo", paywall.key().as_ref(), code.as_bytes()],
+        bump,
+        close = creator
+    )]
+    pub promo: Account<'info, Promo>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(content_id: String, code: String)]
+pub struct UnlockPaywallWithPromo<'info> {
+    pub unlock: UnlockPaywall<'info>,
+    #[account(
+        mut,
+        seeds = [b"promo", unlock.paywall.key().as_ref(), code.as_bytes()],
+        bump
+    )]
+    pub promo: Account<'info, Promo>,
+}
+
+#[derive(Accounts)]
+#[instruction(content_id: String)]
+pub struct GrantAccess<'info> {
+    #[account(
+        mut,
+        seeds = [b"paywall", creator.key().as_ref(), content_id.as_bytes()],
+        bump,
+        has_one = creator,
+        constraint = !paywall.closed @ ErrorCode::PaywallClosed
+    )]
+    pub paywall: Account<'info, Paywall>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(content_id: String)]
+pub struct RevokeAccess<'info> {
+    #[account(
+        mut,
+        seeds = [b"paywall", creator.key().as_ref(), content_id.as_bytes()],
+        bump,
+        has_one = creator
+    )]
+    pub paywall: Account<'info, Paywall>,
+    #[account(
+        mut,
+        seeds = [b"access_receipt", paywall.key().as_ref(), user.key().as_ref()],
+        bump = access_receipt.bump,
+        constraint = access_receipt.kind == AccessKind::Grant @ ErrorCode::InvalidGrant,
+        close = creator
+    )]
+    pub access_receipt: Account<'info, AccessReceipt>,
+    /// CHECK: Only used as a seed for the receipt being revoked
+    pub user: AccountInfo<'info>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(content_id: String)]
+pub struct RequestRefund<'info> {
+    #[account(
+        mut,
+        seeds = [b"paywall", paywall.creator.as_ref(), content_id.as_bytes()],
+        bump
+    )]
+    pub paywall: Account<'info, Paywall>,
+    #[account(
+        mut,
+        seeds = [b"access_receipt", paywall.key().as_ref(), user.key().as_ref()],
+        bump = access_receipt.bump,
+        close = user
+    )]
+    pub access_receipt: Account<'info, AccessReceipt>,
+    #[account(
+        mut,
+        seeds = [b"unlock_vault", access_receipt.key().as_ref()],
+        bump
+    )]
+    pub unlock_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"mint_stats", user.key().as_ref(), access_receipt.token_mint.as_ref()],
+        bump
+    )]
+    pub user_stats: Account<'info, MintStats>,
+    #[account(
+        mut,
+        seeds = [b"mint_stats", paywall.creator.as_ref(), access_receipt.token_mint.as_ref()],
+        bump
+    )]
+    pub creator_stats: Account<'info, MintStats>,
+    // Required if the unlock included a pay-what-you-want tip
+    #[account(
+        mut,
+        seeds = [b"user_profile", paywall.creator.as_ref()],
+        bump
+    )]
+    pub creator_profile: Option<Account<'info, UserProfile>>,
+    /// CHECK: Promo redeemed by the unlock, required if there was one;
+    /// checked against the receipt and may since have been closed
+    #[account(mut)]
+    pub promo: Option<AccountInfo<'info>>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(content_id: String)]
+pub struct SettleUnlock<'info> {
+    #[account(
+        mut,
+        seeds = [b"paywall", paywall.creator.as_ref(), content_id.as_bytes()],
+        bump
+    )]
+    pub paywall: Account<'info, Paywall>,
+    #[account(
+        mut,
+        seeds = [b"access_receipt", paywall.key().as_ref(), user.key().as_ref()],
+        bump = access_receipt.bump
+    )]
+    pub access_receipt: Account<'info, AccessReceipt>,
+    #[account(
+        mut,
+        seeds = [b"unlock_vault", access_receipt.key().as_ref()],
+        bump
+    )]
+    pub unlock_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"platform_config"], bump)]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == paywall.creator
+            @ ErrorCode::InvalidTokenOwner
+    )]
+    pub creator_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = fee_token_account.owner == platform_config.fee_recipient
+            @ ErrorCode::InvalidFeeRecipient
+    )]
+    pub fee_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Buyer who paid the vault rent; bound to the receipt through its seeds
+    #[account(mut)]
+    pub user: AccountInfo<'info>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(bundle_id: String)]
+pub struct CreateBundle<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Bundle::INIT_SPACE,
+        seeds = [b"bundle", creator.key().as_ref(), bundle_id.as_bytes()],
+        bump
+    )]
+    pub bundle: Account<'info, Bundle>,
+    #[account(
+        seeds = [b"platform_config"],
+        bump,
+        constraint = !platform_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(bundle_id: String)]
+pub struct UnlockBundle<'info> {
+    #[account(
+        mut,
+        seeds = [b"bundle", bundle.creator.as_ref(), bundle_id.as_bytes()],
+        bump
+    )]
+    pub bundle: Account<'info, Bundle>,
+    #[account(
+        seeds = [b"platform_config"],
+        bump,
+        constraint = !platform_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == bundle.creator
+            @ ErrorCode::InvalidTokenOwner
+    )]
+    pub creator_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = fee_token_account.owner == platform_config.fee_recipient
+            @ ErrorCode::InvalidFeeRecipient
+    )]
+    pub fee_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = user.key() != bundle.creator @ ErrorCode::SelfPayment
+    )]
+    pub user: Signer<'info>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(content_id: String)]
+pub struct VerifyAccess<'info> {
+    #[account(
+        seeds = [b"paywall", paywall.creator.as_ref(), content_id.as_bytes()],
+        bump
+    )]
+    pub paywall: Account<'info, Paywall>,
+    #[account(
+        seeds = [b"access_receipt", paywall.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub access_receipt: Account<'info, AccessReceipt>,
+    /// CHECK: Only used as a seed; the user does not need to sign
+    pub user: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(content_id: String)]
+pub struct VerifyAccessPass<'info> {
+    #[account(
+        seeds = [b"paywall", paywall.creator.as_ref(), content_id.as_bytes()],
+        bump
+    )]
+    pub paywall: Account<'info, Paywall>,
+    #[account(
+        seeds = [b"access_pass", paywall.key().as_ref()],
+        bump = paywall.access_pass_bump
+    )]
+    pub access_pass_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        constraint = holder_pass_account.mint == access_pass_mint.key()
+            @ ErrorCode::InvalidAccessPass,
+        constraint = holder_pass_account.owner == holder.key() @ ErrorCode::InvalidTokenOwner
+    )]
+    pub holder_pass_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Only checked against the pass account owner; does not need to sign
+    pub holder: AccountInfo<'info>,
+}
+
+// Data structures
+#[account]
+#[derive(InitSpace)]
+pub struct PlatformConfig {
+    pub admin: Pubkey,          // Platform administrator
+    pub pending_admin: Pubkey,  // Proposed admin awaiting acceptance (default if none)
+    pub paused: bool,           // Whether tips and paywalls are paused
+    pub fee_bps: u16,           // Platform fee in basis points
+    pub fee_recipient: Pubkey,  // Owner of the fee token account for each mint
+    pub max_tip_amount: u64,    // Maximum amount per tip (0 for no limit)
+    pub max_paywall_price: u64, // Maximum paywall price (0 for no limit)
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct UserProfile {
+    pub owner: Pubkey,          // User's public key
+    pub interaction_count: u64, // Number of interactions (tips received)
+    #[max_len(32)]
+    pub display_name: String, // Display name, up to MAX_DISPLAY_NAME_LEN bytes
+    #[max_len(200)]
+    pub avatar_uri: String, // Avatar URI, up to MAX_AVATAR_URI_LEN bytes
+    pub bio_hash: [u8; 32],     // Hash of the off-chain bio
+    #[max_len(8)]
+    pub accepted_mints: Vec<Pubkey>, // Mints the user prefers to be tipped in
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Paywall {
+    pub creator: Pubkey, // Creator's public key
+    #[max_len(32)]
+    pub content_id: String, // Unique content identifier
+    pub price: u64,      // Price in tokens
+    pub token_mint: Pubkey, // SPL token mint for payments
+    pub access_count: u64, // Number of users who unlocked
+    #[max_len(5)]
+    pub payees: Vec<SplitShare>, // Co-creators sharing unlock revenue
+    pub refund_window: i64, // Seconds unlock payments stay refundable (0 for none)
+    pub pending_settlements: u64, // Escrowed unlocks not yet settled or refunded
+    pub schedule: Option<PricingSchedule>, // Time-based pricing, if any
+    pub pay_what_you_want: bool, // Whether the price is only a minimum
+    #[max_len(4)]
+    pub mint_prices: Vec<MintPrice>, // Prices in mints accepted besides token_mint
+    pub price_feed: Option<Pubkey>, // If set, `price` is in US cents, converted with this feed
+    pub access_pass: bool, // Whether unlocks mint an access pass token
+    pub access_pass_bump: u8, // Bump of the access pass mint PDA
+    pub royalty_bps: u16, // Creator royalty on secondary pass sales, in basis points
+    pub granted_count: u64, // Users holding free access from grant_access
+    pub promo_count: u32, // Open promo codes
+    pub closed: bool,    // Whether the paywall was closed and can no longer be unlocked
+}
+
+impl Paywall {
+    fn accepts_mint(&self, token_mint: Pubkey) -> bool {
+        token_mint == self.token_mint || self.mint_prices.iter().any(|p| p.token_mint == token_mint)
+    }
+
+    // Price charged for an unlock in the given mint at the given time, after
+    // applying the pricing schedule, or None if the mint is not accepted.
+    // Early-bird prices are set in the primary mint and scale the other
+    // mints' prices by the same ratio
+    fn current_price(&self, token_mint: Pubkey, now: i64) -> Option<u64> {
+        let list_price = if token_mint == self.token_mint {
+            self.price
+        } else {
+            self.mint_prices
+                .iter()
+                .find(|p| p.token_mint == token_mint)?
+                .price
+        };
+        let price = match self.schedule {
+            Some(PricingSchedule::EarlyBird { price, until }) if now < until && self.price > 0 => {
+                (list_price as u128 * price as u128 / self.price as u128) as u64
+            }
+            Some(PricingSchedule::Decay { start, duration }) if now > start => {
+                let elapsed = now.saturating_sub(start).min(duration);
+                let remaining = (duration - elapsed) as u128;
+                (list_price as u128 * remaining / duration as u128) as u64
+            }
+            _ => list_price,
+        };
+        Some(price)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct MintPrice {
+    pub token_mint: Pubkey, // Accepted mint
+    pub price: u64,         // Price in that mint
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum PricingSchedule {
+    // Reduced price until a Unix timestamp, then the paywall price
+    EarlyBird { price: u64, until: i64 },
+    // Paywall price until `start`, then decaying linearly to free over
+    // `duration` seconds
+    Decay { start: i64, duration: i64 },
+}
+
+impl PricingSchedule {
+    fn validate(&self, list_price: u64) -> Result<()> {
+        let valid = match *self {
+            PricingSchedule::EarlyBird { price, .. } => price < list_price,
+            PricingSchedule::Decay { start, duration } => start >= 0 && duration > 0,
+        };
+        if !valid {
+            return err!(ErrorCode::InvalidPricingSchedule);
+        }
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Promo {
+    pub paywall: Pubkey, // Paywall the promo applies to
+    #[max_len(32)]
+    pub code: String, // Promo code
+    pub discount_percent: u8, // Percentage taken off the price
+    pub max_redemptions: u32, // Maximum redemptions (0 for unlimited)
+    pub redemptions: u32, // Number of times redeemed
+    pub expires_at: i64, // Unix timestamp after which the promo is invalid (0 for never)
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct AccessReceipt {
+    pub paywall: Pubkey,       // Paywall that was unlocked
+    pub user: Pubkey,          // User who holds access
+    pub amount: u64,           // Amount paid at unlock
+    pub unlocked_at: i64,      // Unix timestamp of the unlock
+    pub token_mint: Pubkey,    // Mint the unlock was paid in
+    pub refundable_until: i64, // Unix timestamp until which a refund can be requested
+    pub settled: bool,         // Whether the payment has been released from escrow
+    pub bump: u8,              // PDA bump, used to sign for the unlock vault
+    pub kind: AccessKind,      // How access was obtained
+    pub payer: Pubkey,         // Wallet that paid, differs from user for gifts
+    pub tip: u64,              // Pay-what-you-want excess included in amount
+    pub promo: Option<Pubkey>, // Promo redeemed for the unlock, if any
+    pub pass_minted: bool,     // Whether an access pass was minted, which then carries access
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum AccessKind {
+    Purchase, // Paid through unlock_paywall
+    Bundle,   // Included in a bundle unlock
+    Gift,     // Paid for by another wallet through gift_unlock
+    Grant,    // Free access granted by the creator
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Bundle {
+    pub creator: Pubkey, // Creator owning every paywall in the bundle
+    #[max_len(32)]
+    pub bundle_id: String, // Unique bundle identifier
+    pub price: u64,      // Price in tokens for the whole bundle
+    pub token_mint: Pubkey, // SPL token mint for payments
+    #[max_len(10)]
+    pub paywalls: Vec<Pubkey>, // Paywalls unlocked by the bundle
+    pub access_count: u64, // Number of bundle unlocks
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct SubscriptionPlan {
+    pub creator: Pubkey,       // Creator offering the plan
+    pub token_mint: Pubkey,    // SPL token mint for payments
+    pub price_per_period: u64, // Price in tokens per period
+    pub period_seconds: i64,   // Length of one period
+    pub subscriber_count: u64, // Number of distinct subscribers
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Subscription {
+    pub plan: Pubkey,       // Subscription plan
+    pub subscriber: Pubkey, // Subscriber's public key
+    pub started_at: i64,    // Unix timestamp of the first subscription
+    pub expires_at: i64,    // Unix timestamp when access lapses
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct TipEscrow {
+    pub sender: Pubkey,     // Sender who funded the escrow
+    pub recipient: Pubkey,  // Recipient allowed to claim
+    pub token_mint: Pubkey, // SPL token mint held in the vault
+    pub amount: u64,        // Total amount escrowed
+    pub expires_at: i64,    // Unix timestamp after which the sender can reclaim
+    pub bump: u8,           // PDA bump, used to sign for the vault
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct RevenueSplit {
+    pub authority: Pubkey, // Creator of the split
+    #[max_len(32)]
+    pub split_id: String, // Unique split identifier
+    #[max_len(10)]
+    pub recipients: Vec<SplitShare>, // Recipients and their shares
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct SplitShare {
+    pub recipient: Pubkey, // Recipient wallet
+    pub share_bps: u16,    // Share in basis points
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SplitPayment {
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub net_amount: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct MintStats {
+    pub user: Pubkey,        // User the stats belong to
+    pub token_mint: Pubkey,  // Mint the stats are tracked in
+    pub total_received: u64, // Total tipped or paid to the user
+    pub total_sent: u64,     // Total tipped or paid by the user
+    pub received_count: u64, // Number of tips and unlocks received
+    pub sent_count: u64,     // Number of tips and unlocks sent
+    pub last_tip_at: i64,    // Unix timestamp of the latest tip or unlock
+}
+
+impl MintStats {
+    // Sets the owner fields the first time an init_if_needed account is used
+    fn ensure_initialized(&mut self, user: Pubkey, token_mint: Pubkey) {
+        if self.user == Pubkey::default() {
+            self.user = user;
+            self.token_mint = token_mint;
+        }
+    }
+
+    fn record_sent(
+        &mut self,
+        user: Pubkey,
+        token_mint: Pubkey,
+        amount: u64,
+        now: i64,
+    ) -> Result<()> {
+        self.ensure_initialized(user, token_mint);
+        self.total_sent = self
+            .total_sent
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.sent_count += 1;
+        self.last_tip_at = now;
+        Ok(())
+    }
+
+    fn record_received(
+        &mut self,
+        user: Pubkey,
+        token_mint: Pubkey,
+        amount: u64,
+        now: i64,
+    ) -> Result<()> {
+        self.ensure_initialized(user, token_mint);
+        self.total_received = self
+            .total_received
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.received_count += 1;
+        self.last_tip_at = now;
+        Ok(())
+    }
+
+    fn revert_sent(&mut self, amount: u64) -> Result<()> {
+        self.total_sent = self
+            .total_sent
+            .checked_sub(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.sent_count -= 1;
+        Ok(())
+    }
+
+    fn revert_received(&mut self, amount: u64) -> Result<()> {
+        self.total_received = self
+            .total_received
+            .checked_sub(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.received_count -= 1;
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct SupporterTotal {
+    pub recipient: Pubkey,  // Recipient of the tips
+    pub supporter: Pubkey,  // Sender of the tips
+    pub token_mint: Pubkey, // Mint the total is tracked in
+    pub total: u64,         // Total tipped by the supporter to the recipient
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Leaderboard {
+    pub recipient: Pubkey,  // Recipient the leaderboard belongs to
+    pub token_mint: Pubkey, // Mint the totals are tracked in
+    #[max_len(10)]
+    pub entries: Vec<LeaderboardEntry>, // Top supporters, highest total first
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct LeaderboardEntry {
+    pub supporter: Pubkey, // Supporter's public key
+    pub total: u64,        // Total tipped by the supporter
+}
+
+impl Leaderboard {
+    // Records a supporter's new running total, keeping only the top entries
+    fn record(&mut self, supporter: Pubkey, total: u64) {
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.supporter == supporter) {
+            entry.total = total;
+        } else if self.entries.len() < LEADERBOARD_SIZE {
+            self.entries.push(LeaderboardEntry { supporter, total });
+        } else if let Some(last) = self.entries.last_mut() {
+            if total <= last.total {
+                return;
+            }
+            *last = LeaderboardEntry { supporter, total };
+        }
+        self.entries.sort_by_key(|e| std::cmp::Reverse(e.total));
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Pledge {
+    pub sender: Pubkey,               // Supporter making the pledge
+    pub recipient: Pubkey,            // Recipient of the pledged tips
+    pub token_mint: Pubkey,           // SPL token mint for payments
+    pub sender_token_account: Pubkey, // Token account the pledge PDA is delegate on
+    pub amount: u64,                  // Amount tipped per interval
+    pub interval_seconds: i64,        // Length of one interval
+    pub next_due_at: i64,             // Unix timestamp the next payment is due
+    pub executed_count: u64,          // Number of payments made
+    pub missed_periods: u64,          // Number of intervals that passed unpaid
+    pub bump: u8,                     // PDA bump, used to sign as delegate
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Campaign {
+    pub creator: Pubkey, // Creator running the campaign
+    #[max_len(32)]
+    pub campaign_id: String, // Unique campaign identifier
+    pub token_mint: Pubkey, // SPL token mint for contributions
+    pub target_amount: u64, // Amount needed for the campaign to succeed
+    pub deadline: i64,   // Unix timestamp contributions close
+    pub total_raised: u64, // Total contributed so far
+    pub contributor_count: u64, // Number of distinct contributors
+    pub finalized: bool, // Whether funds have been released to the creator
+    pub bump: u8,        // PDA bump, used to sign for the vault
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Contribution {
+    pub campaign: Pubkey,    // Campaign contributed to
+    pub contributor: Pubkey, // Contributor's public key
+    pub amount: u64,         // Total contributed, refundable if the campaign fails
+}
+
+// Events for frontend integration
+#[event]
+pub struct ProfileUpdated {
+    pub owner: Pubkey,
+    pub display_name: String,
+    pub avatar_uri: String,
+    pub bio_hash: [u8; 32],
+    pub accepted_mints: Vec<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TipEvent {
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub net_amount: u64, // Amount received after platform and transfer fees
+    pub action: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PaywallUnlockEvent {
+    pub user: Pubkey,        // Payer
+    pub beneficiary: Pubkey, // Holder of the access, equal to user unless gifted
+    pub creator: Pubkey,
+    pub content_id: String,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub net_amount: u64, // Amount received after platform and transfer fees
+    pub payments: Vec<SplitPayment>, // Per-payee breakdown, empty if the creator takes all
+    pub refundable_until: i64, // Equal to timestamp if the payment was not escrowed
+    pub scheduled_price: u64, // Price from the pricing schedule, before any promo
+    pub tip: u64,        // Amount paid above the price on pay-what-you-want paywalls
+    pub discount: u64,   // Amount taken off the paywall price
+    pub promo: Option<Pubkey>, // Promo redeemed, if any
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccessGranted {
+    pub creator: Pubkey,
+    pub content_id: String,
+    pub users: Vec<Pubkey>, // Wallets newly granted access
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccessRevoked {
+    pub creator: Pubkey,
+    pub content_id: String,
+    pub user: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UnlockRefunded {
+    pub user: Pubkey,
+    pub creator: Pubkey,
+    pub content_id: String,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UnlockSettled {
+    pub user: Pubkey,
+    pub creator: Pubkey,
+    pub content_id: String,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub net_amount: u64,
+    pub payments: Vec<SplitPayment>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PaywallUpdated {
+    pub creator: Pubkey,
+    pub content_id: String,
+    pub price: u64,
+    pub token_mint: Pubkey,
+    pub pay_what_you_want: bool,
+    pub price_feed: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MintPricesUpdated {
+    pub creator: Pubkey,
+    pub content_id: String,
+    pub mint_prices: Vec<MintPrice>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PricingScheduleUpdated {
+    pub creator: Pubkey,
+    pub content_id: String,
+    pub schedule: Option<PricingSchedule>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccessPassEnabled {
+    pub creator: Pubkey,
+    pub content_id: String,
+    pub mint: Pubkey,
+    pub royalty_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PaywallClosed {
+    pub creator: Pubkey,
+    pub content_id: String,
+    pub access_count: u64,
+    pub account_closed: bool, // False if the paywall was only marked closed
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubscriptionEvent {
+    pub subscriber: Pubkey,
+    pub creator: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub net_amount: u64,
+    pub periods: u32,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TipSplitEvent {
+    pub sender: Pubkey,
+    pub revenue_split: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub payments: Vec<SplitPayment>,
+    pub action: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PledgeCreated {
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub interval_seconds: i64,
+    pub max_periods: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PledgeCancelled {
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub token_mint: Pubkey,
+    pub executed_count: u64,
+    pub missed_periods: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CampaignCreated {
+    pub creator: Pubkey,
+    pub campaign_id: String,
+    pub token_mint: Pubkey,
+    pub target_amount: u64,
+    pub deadline: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CampaignContribution {
+    pub contributor: Pubkey,
+    pub creator: Pubkey,
+    pub campaign_id: String,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub total_raised: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CampaignFinalized {
+    pub creator: Pubkey,
+    pub campaign_id: String,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub net_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ContributionRefunded {
+    pub contributor: Pubkey,
+    pub creator: Pubkey,
+    pub campaign_id: String,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BundleUnlockEvent {
+    pub user: Pubkey,
+    pub creator: Pubkey,
+    pub bundle_id: String,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub net_amount: u64,
+    pub paywalls: Vec<Pubkey>, // Paywalls newly unlocked by this purchase
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TipEscrowed {
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub action: String,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TipsClaimed {
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub net_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TipReclaimed {
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+// Custom errors
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Invalid token mint provided")]
+    InvalidTokenMint,
+    #[msg("No access receipt found for this user and paywall")]
+    AccessNotFound,
+    #[msg("Fee basis points cannot exceed 10,000")]
+    InvalidFeeBps,
+    #[msg("Fee token account is not owned by the platform fee recipient")]
+    InvalidFeeRecipient,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("The program is paused")]
+    ProgramPaused,
+    #[msg("Amount exceeds the configured limit")]
+    AmountExceedsLimit,
+    #[msg("Signer is not authorized for this action")]
+    Unauthorized,
+    #[msg("Period must be greater than zero")]
+    InvalidPeriod,
+    #[msg("Token account is not owned by the expected wallet")]
+    InvalidTokenOwner,
+    #[msg("Escrowed tip has not expired yet")]
+    TipNotExpired,
+    #[msg("Display name is too long")]
+    DisplayNameTooLong,
+    #[msg("Avatar URI is too long")]
+    AvatarUriTooLong,
+    #[msg("Too many accepted mints")]
+    TooManyAcceptedMints,
+    #[msg("Split shares must be non-zero, unique and sum to 10,000 bps")]
+    InvalidSplitShares,
+    #[msg("Too many split recipients")]
+    TooManySplitRecipients,
+    #[msg("Remaining accounts do not match the split recipients")]
+    InvalidSplitAccounts,
+    #[msg("Refund window must be between zero and 30 days")]
+    InvalidRefundWindow,
+    #[msg("Unlock vault must be passed only for paywalls with a refund window")]
+    InvalidUnlockVault,
+    #[msg("Unlock has already been settled")]
+    UnlockAlreadySettled,
+    #[msg("Refund window has closed")]
+    RefundWindowClosed,
+    #[msg("Refund window is still open")]
+    RefundWindowOpen,
+    #[msg("Paywall has unlocks pending settlement")]
+    PendingSettlements,
+    #[msg("Sender and recipient must be different")]
+    SelfPayment,
+    #[msg("Pledge is not due yet")]
+    PledgeNotDue,
+    #[msg("Campaign needs a non-zero target and a future deadline")]
+    InvalidCampaign,
+    #[msg("Campaign is no longer accepting contributions")]
+    CampaignClosed,
+    #[msg("Campaign has not reached its target")]
+    CampaignTargetNotMet,
+    #[msg("Campaign has not failed")]
+    CampaignNotFailed,
+    #[msg("Bundle paywalls are invalid or do not match the accounts passed")]
+    InvalidBundle,
+    #[msg("Promo discount must be between 1 and 100 percent")]
+    InvalidPromo,
+    #[msg("Promo has expired")]
+    PromoExpired,
+    #[msg("Promo has no redemptions left")]
+    PromoExhausted,
+    #[msg("Invalid pricing schedule")]
+    InvalidPricingSchedule,
+    #[msg("Amount is below the paywall price")]
+    AmountBelowPrice,
+    #[msg("Creator profile is required to record a tip")]
+    CreatorProfileRequired,
+    #[msg("Too many accepted mints")]
+    TooManyPaywallMints,
+    #[msg("Invalid price feed")]
+    InvalidPriceFeed,
+    #[msg("Price feed is stale")]
+    StalePrice,
+    #[msg("Price feed confidence interval is too wide")]
+    PriceConfidenceTooWide,
+    #[msg("Royalty exceeds 100%")]
+    InvalidRoyaltyBps,
+    #[msg("Invalid access pass account")]
+    InvalidAccessPass,
+    #[msg("Invalid gift beneficiary")]
+    InvalidBeneficiary,
+    #[msg("Invalid access grant")]
+    InvalidGrant,
+    #[msg("Paywall is closed")]
+    PaywallClosed,
+    #[msg("Token account already has a delegate")]
+    DelegateInUse,
+    #[msg("Amount must be greater than zero")]
+    ZeroAmount,
+    #[msg("Access is held by the paywall's access pass, not the receipt")]
+    AccessHeldByPass,
+}
+
+// Helpers
+fn calculate_fee(amount: u64, fee_bps: u16) -> Result<u64> {
+    let fee = (amount as u128)
+        .checked_mul(fee_bps as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        / BPS_DENOMINATOR as u128;
+    Ok(fee as u64)
+}
+
+fn validate_split_shares(shares: &[SplitShare]) -> Result<()> {
+    if shares.len() > MAX_SPLIT_RECIPIENTS {
+        return err!(ErrorCode::TooManySplitRecipients);
+    }
+    let mut total: u64 = 0;
+    for (i, share) in shares.iter().enumerate() {
+        if share.share_bps == 0 || shares[..i].iter().any(|s| s.recipient == share.recipient) {
+            return err!(ErrorCode::InvalidSplitShares);
+        }
+        total += share.share_bps as u64;
+    }
+    if total != BPS_DENOMINATOR {
+        return err!(ErrorCode::InvalidSplitShares);
+    }
+    Ok(())
+}
+
+// Divides `amount` by basis-point shares, rounding down, and returns the
+// rounding dust left over separately
+fn split_amount(amount: u64, shares: &[u16]) -> Result<(Vec<u64>, u64)> {
+    let mut amounts = Vec::with_capacity(shares.len());
+    for share_bps in shares {
+        let share_amount = (amount as u128)
+            .checked_mul(*share_bps as u128)
+            .ok_or(ErrorCode::MathOverflow)?
+            / BPS_DENOMINATOR as u128;
+        amounts.push(share_amount as u64);
+    }
+    let distributed: u64 = amounts.iter().sum();
+    Ok((amounts, amount - distributed))
+}
+
+// Loads the token accounts passed as remaining accounts for each share,
+// checking they belong to the share's recipient and use the expected mint
+fn load_share_accounts<'info>(
+    shares: &[SplitShare],
+    accounts: &'info [AccountInfo<'info>],
+    mint: &Pubkey,
+) -> Result<Vec<InterfaceAccount<'info, TokenAccount>>> {
+    if accounts.len() != shares.len() {
+        return err!(ErrorCode::InvalidSplitAccounts);
+    }
+    let mut token_accounts = Vec::with_capacity(shares.len());
+    for (share, account_info) in shares.iter().zip(accounts.iter()) {
+        let token_account = InterfaceAccount::<TokenAccount>::try_from(account_info)?;
+        if token_account.owner != share.recipient {
+            return err!(ErrorCode::InvalidTokenOwner);
+        }
+        if token_account.mint != *mint {
+            return err!(ErrorCode::InvalidTokenMint);
+        }
+        token_accounts.push(token_account);
+    }
+    Ok(token_accounts)
+}
+
+// Creates an access receipt at its PDA outside of an Accounts context, for
+// instructions that issue receipts for a variable number of paywalls
+fn init_access_receipt<'info>(
+    receipt_info: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    receipt: &AccessReceipt,
+) -> Result<()> {
+    let space = 8 + AccessReceipt::INIT_SPACE;
+    let rent = Rent::get()?.minimum_balance(space);
+    let signer_seeds: &[&[u8]] = &[
+        b"access_receipt",
+        receipt.paywall.as_ref(),
+        receipt.user.as_ref(),
+        &[receipt.bump],
+    ];
+    let accounts = [
+        payer.to_account_info(),
+        receipt_info.clone(),
+        system_program.to_account_info(),
+    ];
+    let current_lamports = receipt_info.lamports();
+    if current_lamports == 0 {
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                receipt_info.key,
+                rent,
+                space as u64,
+                &crate::ID,
+            ),
+            &accounts,
+            &[signer_seeds],
+        )?;
+    } else {
+        // Someone sent lamports to the address first, which would make
+        // create_account fail; top up, allocate and assign instead, as
+        // Anchor's init does
+        let top_up = rent.saturating_sub(current_lamports);
+        if top_up > 0 {
+            invoke(
+                &system_instruction::transfer(payer.key, receipt_info.key, top_up),
+                &accounts,
+            )?;
+        }
+        invoke_signed(
+            &system_instruction::allocate(receipt_info.key, space as u64),
+            &accounts,
+            &[signer_seeds],
+        )?;
+        invoke_signed(
+            &system_instruction::assign(receipt_info.key, &crate::ID),
+            &accounts,
+            &[signer_seeds],
+        )?;
+    }
+    let mut data = receipt_info.try_borrow_mut_data()?;
+    receipt.try_serialize(&mut &mut data[..])
+}
+
+// Price for an unlock at up to `amount`: the current price, or all of
+// `amount` on pay-what-you-want paywalls with the excess as a tip
+fn pay_what_you_want_price(accounts: &UnlockPaywall, amount: u64) -> Result<UnlockPrice> {
+    let now = Clock::get()?.unix_timestamp;
+    let floor = resolve_unlock_price(accounts, now)?;
+    if amount < floor {
+        return err!(ErrorCode::AmountBelowPrice);
+    }
+    let tip = if accounts.paywall.pay_what_you_want {
+        amount - floor
+    } else {
+        0
+    };
+    let max_tip_amount = accounts.platform_config.max_tip_amount;
+    if max_tip_amount > 0 && tip > max_tip_amount {
+        return err!(ErrorCode::AmountExceedsLimit);
+    }
+
+    Ok(UnlockPrice {
+        amount: floor + tip,
+        discount: 0,
+        tip,
+        promo: None,
+    })
+}
+
+// Price charged for an unlock, after any discount
+struct UnlockPrice {
+    amount: u64,
+    discount: u64,
+    tip: u64,
+    promo: Option<Pubkey>,
+}
+
+// Takes payment for an unlock at the given price and issues the access
+// receipt; shared by the unlock_paywall variants
+fn process_unlock<'info>(
+    accounts: &mut UnlockPaywall<'info>,
+    remaining_accounts: &'info [AccountInfo<'info>],
+    receipt_bump: u8,
+    content_id: String,
+    price: UnlockPrice,
+) -> Result<()> {
+    let paywall = &mut accounts.paywall;
+    let amount = price.amount;
+
+    // Validate token mint matches paywall and token accounts
+    let token_mint = accounts.token_mint.key();
+    if !paywall.accepts_mint(token_mint)
+        || accounts.user_token_account.mint != accounts.token_mint.key()
+        || accounts.creator_token_account.mint != accounts.token_mint.key()
+        || accounts.fee_token_account.mint != accounts.token_mint.key()
+    {
+        return err!(ErrorCode::InvalidTokenMint);
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    let beneficiary = accounts.beneficiary.as_ref().map(|b| b.key());
+    // Checked on every unlock path, as a gift to oneself would skip the
+    // refund escrow
+    if let Some(beneficiary) = beneficiary {
+        if beneficiary == accounts.user.key() || beneficiary == paywall.creator {
+            return err!(ErrorCode::InvalidBeneficiary);
+        }
+    }
+    let holder = beneficiary.unwrap_or(accounts.user.key());
+    let escrowed = paywall.refund_window > 0 && beneficiary.is_none();
+    let (fee, net_amount, payments, refundable_until) = if escrowed {
+        // Hold the full price in escrow until settle_unlock
+        let unlock_vault = accounts
+            .unlock_vault
+            .as_mut()
+            .ok_or(ErrorCode::InvalidUnlockVault)?;
+        transfer_tokens(
+            &accounts.token_program,
+            &accounts.user_token_account,
+            unlock_vault,
+            &accounts.user,
+            &accounts.token_mint,
+            amount,
+        )?;
+        paywall.pending_settlements += 1;
+        (0, 0, Vec::new(), now + paywall.refund_window)
+    } else {
+        if accounts.unlock_vault.is_some() {
+            return err!(ErrorCode::InvalidUnlockVault);
+        }
+        let source = PaymentSource {
+            token_program: &accounts.token_program,
+            from: &accounts.user_token_account,
+            authority: &accounts.user,
+            mint: &accounts.token_mint,
+            signer_seeds: &[],
+        };
+        let (fee, net_amount, payments) = distribute_paywall_payment(
+            &source,
+            &paywall.payees,
+            remaining_accounts,
+            &mut accounts.creator_token_account,
+            &mut accounts.fee_token_account,
+            amount,
+            accounts.platform_config.fee_bps,
+        )?;
+        (fee, net_amount, payments, now)
+    };
+
+    // Update paywall access count and per-mint stats for both parties
+    paywall.access_count += 1;
+    accounts
+        .user_stats
+        .record_sent(accounts.user.key(), token_mint, amount, now)?;
+    accounts
+        .creator_stats
+        .record_received(paywall.creator, token_mint, amount, now)?;
+
+    // A pay-what-you-want excess counts as a tip to the creator
+    if price.tip > 0 {
+        let creator_profile = accounts
+            .creator_profile
+            .as_mut()
+            .ok_or(ErrorCode::CreatorProfileRequired)?;
+        creator_profile.interaction_count += 1;
+    }
+
+    // Record the unlock so access can be verified on-chain
+    let access_receipt = &mut accounts.access_receipt;
+    access_receipt.paywall = paywall.key();
+    access_receipt.user = holder;
+    access_receipt.amount = amount;
+    access_receipt.unlocked_at = now;
+    access_receipt.token_mint = token_mint;
+    access_receipt.refundable_until = refundable_until;
+    access_receipt.settled = !escrowed;
+    access_receipt.bump = receipt_bump;
+    access_receipt.kind = if beneficiary.is_some() {
+        AccessKind::Gift
+    } else {
+        AccessKind::Purchase
+    };
+    access_receipt.payer = accounts.user.key();
+    access_receipt.tip = price.tip;
+    access_receipt.promo = price.promo;
+    access_receipt.pass_minted = paywall.access_pass;
+
+    if paywall.access_pass {
+        let (Some(pass_mint), Some(user_pass_account), Some(pass_token_program)) = (
+            accounts.access_pass_mint.as_ref(),
+            accounts.user_pass_account.as_ref(),
+            accounts.pass_token_program.as_ref(),
+        ) else {
+            return err!(ErrorCode::InvalidAccessPass);
+        };
+        if user_pass_account.mint != pass_mint.key()
+            || *pass_mint.to_account_info().owner != pass_token_program.key()
+        {
+            return err!(ErrorCode::InvalidAccessPass);
+        }
+        if user_pass_account.owner != holder {
+            return err!(ErrorCode::InvalidTokenOwner);
+        }
+        let paywall_key = paywall.key();
+        mint_tokens_signed(
+            pass_token_program,
+            pass_mint,
+            user_pass_account,
+            1,
+            &[&[
+                b"access_pass",
+                paywall_key.as_ref(),
+                &[paywall.access_pass_bump],
+            ]],
+        )?;
+    }
+
+    // Emit event
+    emit!(PaywallUnlockEvent {
+        user: accounts.user.key(),
+        beneficiary: holder,
+        creator: paywall.creator,
+        content_id,
+        token_mint,
+        amount,
+        fee,
+        net_amount,
+        payments,
+        refundable_until,
+        scheduled_price: amount - price.tip + price.discount,
+        tip: price.tip,
+        discount: price.discount,
+        promo: price.promo,
+        timestamp: now,
+    });
+
+    msg!(
+        "Unlocked paywall for content {} for {} by {}",
+        paywall.content_id,
+        holder,
+        accounts.user.key()
+    );
+    Ok(())
+}
+
+// Token amount charged for an unlock in the provided mint, converting
+// USD-priced paywalls at the price feed's current price
+fn resolve_unlock_price(accounts: &UnlockPaywall, now: i64) -> Result<u64> {
+    let paywall = &accounts.paywall;
+    let token_mint = accounts.token_mint.key();
+    let price = paywall
+        .current_price(token_mint, now)
+        .ok_or(ErrorCode::InvalidTokenMint)?;
+    match paywall.price_feed {
+        Some(feed) if token_mint == paywall.token_mint => {
+            let price_feed = accounts
+                .price_feed
+                .as_ref()
+                .filter(|info| info.key() == feed)
+                .ok_or(ErrorCode::InvalidPriceFeed)?;
+            let feed_price = read_price_feed(price_feed, now)?;
+            let amount = usd_cents_to_tokens(price, &feed_price, accounts.token_mint.decimals)?;
+            // Token prices are checked when set; converted ones only now
+            let max_paywall_price = accounts.platform_config.max_paywall_price;
+            if max_paywall_price > 0 && amount > max_paywall_price {
+                return err!(ErrorCode::AmountExceedsLimit);
+            }
+            Ok(amount)
+        }
+        _ => Ok(price),
+    }
+}
+
+// Pyth price account layout
+const PYTH_PRICE_ACCOUNT_LEN: usize = 240;
+const PYTH_MAGIC: u32 = 0xa1b2_c3d4;
+const PYTH_PRICE_ACCOUNT_TYPE: u32 = 3;
+const PYTH_STATUS_TRADING: u32 = 1;
+
+// Aggregate price read from a price feed, worth `price * 10^expo` USD
+struct FeedPrice {
+    price: i64,
+    conf: u64,
+    expo: i32,
+}
+
+// Builds with the `mock-oracle` feature also accept the local test feeds of
+// the mock-pyth program
+fn is_trusted_price_feed_owner(owner: &Pubkey) -> bool {
+    #[cfg(feature = "mock-oracle")]
+    if owner == &mock_pyth::ID {
+        return true;
+    }
+    owner == &PYTH_ORACLE_PROGRAM_ID
+}
+
+// Reads the aggregate price of a Pyth price account, rejecting prices that
+// are not trading, older than MAX_PRICE_AGE or less certain than
+// MAX_PRICE_CONF_BPS
+fn read_price_feed(price_feed: &AccountInfo, now: i64) -> Result<FeedPrice> {
+    if !is_trusted_price_feed_owner(price_feed.owner) {
+        return err!(ErrorCode::InvalidPriceFeed);
+    }
+
+    let data = price_feed.try_borrow_data()?;
+    if data.len() < PYTH_PRICE_ACCOUNT_LEN {
+        return err!(ErrorCode::InvalidPriceFeed);
+    }
+    let read_u32 = |at: usize| u32::from_le_bytes(data[at..at + 4].try_into().unwrap());
+    let read_u64 = |at: usize| u64::from_le_bytes(data[at..at + 8].try_into().unwrap());
+    if read_u32(0) != PYTH_MAGIC
+        || read_u32(8) != PYTH_PRICE_ACCOUNT_TYPE
+        || read_u32(224) != PYTH_STATUS_TRADING
+    {
+        return err!(ErrorCode::InvalidPriceFeed);
+    }
+    let feed_price = FeedPrice {
+        price: read_u64(208) as i64,
+        conf: read_u64(216),
+        expo: read_u32(20) as i32,
+    };
+    let publish_time = read_u64(96) as i64;
+
+    if feed_price.price <= 0 {
+        return err!(ErrorCode::InvalidPriceFeed);
+    }
+    if now - publish_time > MAX_PRICE_AGE {
+        return err!(ErrorCode::StalePrice);
+    }
+    let conf_bps = feed_price.conf as u128 * BPS_DENOMINATOR as u128 / feed_price.price as u128;
+    if conf_bps > MAX_PRICE_CONF_BPS as u128 {
+        return err!(ErrorCode::PriceConfidenceTooWide);
+    }
+    Ok(feed_price)
+}
+
+// Converts a price in US cents to token base units, rounding up so the
+// buyer always pays at least the USD price
+fn usd_cents_to_tokens(usd_cents: u64, feed_price: &FeedPrice, decimals: u8) -> Result<u64> {
+    let pow10 = |exp: u32| 10u128.checked_pow(exp).ok_or(ErrorCode::MathOverflow);
+    // tokens = cents * 10^decimals / (100 * price * 10^expo)
+    let mut numerator = (usd_cents as u128)
+        .checked_mul(pow10(decimals as u32)?)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let mut denominator = feed_price.price as u128 * 100;
+    if feed_price.expo < 0 {
+        numerator = numerator
+            .checked_mul(pow10(feed_price.expo.unsigned_abs())?)
+            .ok_or(ErrorCode::MathOverflow)?;
+    } else {
+        denominator = denominator
+            .checked_mul(pow10(feed_price.expo as u32)?)
+            .ok_or(ErrorCode::MathOverflow)?;
+    }
+    let tokens = numerator.div_ceil(denominator);
+    u64::try_from(tokens).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+// Token account a payment is drawn from, with the authority that can move it
+struct PaymentSource<'a, 'info> {
+    token_program: &'a Interface<'info, TokenInterface>,
+    from: &'a InterfaceAccount<'info, TokenAccount>,
+    authority: &'a AccountInfo<'info>,
+    mint: &'a InterfaceAccount<'info, Mint>,
+    signer_seeds: &'a [&'a [&'a [u8]]],
+}
+
+// Pays a paywall price out to the platform fee, the payee shares and the
+// creator, who also gets the rounding dust (or everything, if there are no
+// payees). Returns the fee, the total net amount received and the per-payee
+// breakdown
+fn distribute_paywall_payment<'info>(
+    source: &PaymentSource<'_, 'info>,
+    payees: &[SplitShare],
+    payee_accounts: &'info [AccountInfo<'info>],
+    creator_token_account: &mut InterfaceAccount<'info, TokenAccount>,
+    fee_token_account: &mut InterfaceAccount<'info, TokenAccount>,
+    amount: u64,
+    fee_bps: u16,
+) -> Result<(u64, u64, Vec<SplitPayment>)> {
+    let payee_token_accounts = load_share_accounts(payees, payee_accounts, &source.mint.key())?;
+
+    let fee = calculate_fee(amount, fee_bps)?;
+    let shares: Vec<u16> = payees.iter().map(|p| p.share_bps).collect();
+    let (amounts, dust) = split_amount(amount - fee, &shares)?;
+
+    let mut payments = Vec::with_capacity(payees.len());
+    for ((payee, payee_amount), mut payee_token_account) in
+        payees.iter().zip(amounts).zip(payee_token_accounts)
+    {
+        let net_amount = transfer_tokens_signed(
+            source.token_program,
+            source.from,
+            &mut payee_token_account,
+            source.authority,
+            source.mint,
+            payee_amount,
+            source.signer_seeds,
+        )?;
+        payments.push(SplitPayment {
+            recipient: payee.recipient,
+            amount: payee_amount,
+            net_amount,
+        });
+    }
+    let creator_net_amount = transfer_tokens_signed(
+        source.token_program,
+        source.from,
+        creator_token_account,
+        source.authority,
+        source.mint,
+        dust,
+        source.signer_seeds,
+    )?;
+    transfer_tokens_signed(
+        source.token_program,
+        source.from,
+        fee_token_account,
+        source.authority,
+        source.mint,
+        fee,
+        source.signer_seeds,
+    )?;
+    let net_amount = payments
+        .iter()
+        .fold(creator_net_amount, |total, p| total + p.net_amount);
+    Ok((fee, net_amount, payments))
+}
+
+// Transfers with transfer_checked and returns the amount `to` actually
+// received, which is less than `amount` for mints with a transfer fee
+fn transfer_tokens<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    to: &mut InterfaceAccount<'info, TokenAccount>,
+    authority: &Signer<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    amount: u64,
+) -> Result<u64> {
+    transfer_tokens_signed(token_program, from, to, authority, mint, amount, &[])
+}
+
+// Same as transfer_tokens, for accounts whose authority is a program PDA
+fn transfer_tokens_signed<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    to: &mut InterfaceAccount<'info, TokenAccount>,
+    authority: &AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<u64> {
+    if amount == 0 {
+        return Ok(0);
+    }
+    // `to` may be another handle on an account that an earlier transfer
+    // already credited, e.g. a creator who is also a payee
+    to.reload()?;
+    let balance_before = to.amount;
+    let cpi_accounts = TransferChecked {
+        from: from.to_account_info(),
+        mint: mint.to_account_info(),
+        to: to.to_account_info(),
+        authority: authority.clone(),
+    };
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer_seeds),
+        amount,
+        mint.decimals,
+    )?;
+    to.reload()?;
+    Ok(to.amount.saturating_sub(balance_before))
+}
+
+fn close_token_account<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    account: &InterfaceAccount<'info, TokenAccount>,
+    destination: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let cpi_accounts = CloseAccount {
+        account: account.to_account_info(),
+        destination: destination.clone(),
+        authority: authority.clone(),
+    };
+    token_interface::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        cpi_accounts,
+        signer_seeds,
+    ))
+}
+
+// Mints tokens from a mint that is its own PDA authority
+fn mint_tokens_signed<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let cpi_accounts = MintTo {
+        mint: mint.to_account_info(),
+        to: to.to_account_info(),
+        authority: mint.to_account_info(),
+    };
+    token_interface::mint_to(
+        CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer_seeds),
+        amount,
+    )
+}
+
+fn transfer_lamports<'info>(
+    from: &Signer<'info>,
+    to: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    invoke(
+        &system_instruction::transfer(from.key, to.key, amount),
+        &[
+            from.to_account_info(),
+            to.clone(),
+            system_program.to_account_info(),
+        ],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn share(recipient: Pubkey, share_bps: u16) -> SplitShare {
+        SplitShare {
+            recipient,
+            share_bps,
+        }
+    }
+
+    #[test]
+    fn split_shares_must_total_100_percent() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert!(validate_split_shares(&[share(a, 6_000), share(b, 4_000)]).is_ok());
+        assert_eq!(
+            validate_split_shares(&[share(a, 6_000), share(b, 3_999)]).unwrap_err(),
+            ErrorCode::InvalidSplitShares.into()
+        );
+        assert_eq!(
+            validate_split_shares(&[share(a, 6_000), share(b, 4_001)]).unwrap_err(),
+            ErrorCode::InvalidSplitShares.into()
+        );
+        assert_eq!(
+            validate_split_shares(&[]).unwrap_err(),
+            ErrorCode::InvalidSplitShares.into()
+        );
+    }
+
+    #[test]
+    fn split_shares_reject_zero_and_duplicate_recipients() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert_eq!(
+            validate_split_shares(&[share(a, 10_000), share(b, 0)]).unwrap_err(),
+            ErrorCode::InvalidSplitShares.into()
+        );
+        assert_eq!(
+            validate_split_shares(&[share(a, 5_000), share(a, 5_000)]).unwrap_err(),
+            ErrorCode::InvalidSplitShares.into()
+        );
+    }
+
+    #[test]
+    fn split_shares_limit_recipients() {
+        let shares: Vec<SplitShare> = (0..=MAX_SPLIT_RECIPIENTS)
+            .map(|_| share(Pubkey::new_unique(), 1))
+            .collect();
+        assert_eq!(
+            validate_split_shares(&shares).unwrap_err(),
+            ErrorCode::TooManySplitRecipients.into()
+        );
+    }
+
+    #[test]
+    fn split_amount_divides_exactly() {
+        let (amounts, dust) = split_amount(1_000, &[5_000, 3_000, 2_000]).unwrap();
+        assert_eq!(amounts, vec![500, 300, 200]);
+        assert_eq!(dust, 0);
+    }
+
+    #[test]
+    fn split_amount_rounds_down_and_returns_dust() {
+        let (amounts, dust) = split_amount(100, &[3_333, 3_333, 3_334]).unwrap();
+        assert_eq!(amounts, vec![33, 33, 33]);
+        assert_eq!(dust, 1);
+
+        // Amounts too small for any share leave everything as dust
+        let (amounts, dust) = split_amount(1, &[5_000, 5_000]).unwrap();
+        assert_eq!(amounts, vec![0, 0]);
+        assert_eq!(dust, 1);
+    }
+
+    #[test]
+    fn split_amount_handles_large_amounts() {
+        let (amounts, dust) = split_amount(u64::MAX, &[5_000, 5_000]).unwrap();
+        assert_eq!(amounts, vec![u64::MAX / 2, u64::MAX / 2]);
+        assert_eq!(dust, 1);
+        assert_eq!(amounts.iter().sum::<u64>() + dust, u64::MAX);
+    }
+
+    const NOW: i64 = 1_700_000_000;
+
+    // Reads a feed written by the mock-pyth program's layout writer
+    fn read_feed(
+        owner: Pubkey,
+        price: i64,
+        conf: u64,
+        expo: i32,
+        publish_time: i64,
+    ) -> Result<FeedPrice> {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = vec![0u8; PYTH_PRICE_ACCOUNT_LEN];
+        mock_pyth::write_price_feed(&mut data, price, conf, expo, publish_time);
+        let info = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+        read_price_feed(&info, NOW)
+    }
+
+    #[test]
+    fn price_feed_is_read_from_the_pyth_layout() {
+        let feed = read_feed(PYTH_ORACLE_PROGRAM_ID, 15_000_000_000, 1_000_000, -8, NOW).unwrap();
+        assert_eq!(feed.price, 15_000_000_000);
+        assert_eq!(feed.conf, 1_000_000);
+        assert_eq!(feed.expo, -8);
+    }
+
+    #[test]
+    fn price_feed_must_be_owned_by_pyth() {
+        let result = read_feed(Pubkey::new_unique(), 100, 0, 0, NOW);
+        assert_eq!(result.err().unwrap(), ErrorCode::InvalidPriceFeed.into());
+    }
+
+    #[test]
+    fn price_feed_must_have_the_pyth_layout() {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = vec![0u8; PYTH_PRICE_ACCOUNT_LEN];
+        let info = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &PYTH_ORACLE_PROGRAM_ID,
+            false,
+            0,
+        );
+        let result = read_price_feed(&info, NOW);
+        assert_eq!(result.err().unwrap(), ErrorCode::InvalidPriceFeed.into());
+    }
+
+    #[test]
+    fn price_feed_price_must_be_positive() {
+        for price in [0, -1] {
+            let result = read_feed(PYTH_ORACLE_PROGRAM_ID, price, 0, 0, NOW);
+            assert_eq!(result.err().unwrap(), ErrorCode::InvalidPriceFeed.into());
+        }
+    }
+
+    #[test]
+    fn price_feed_staleness() {
+        assert!(read_feed(PYTH_ORACLE_PROGRAM_ID, 100, 0, 0, NOW - MAX_PRICE_AGE).is_ok());
+        let result = read_feed(PYTH_ORACLE_PROGRAM_ID, 100, 0, 0, NOW - MAX_PRICE_AGE - 1);
+        assert_eq!(result.err().unwrap(), ErrorCode::StalePrice.into());
+    }
+
+    #[test]
+    fn price_feed_confidence() {
+        // 200 bps of 10_000 is 200
+        assert!(read_feed(PYTH_ORACLE_PROGRAM_ID, 10_000, 200, 0, NOW).is_ok());
+        let result = read_feed(PYTH_ORACLE_PROGRAM_ID, 10_000, 201, 0, NOW);
+        assert_eq!(
+            result.err().unwrap(),
+            ErrorCode::PriceConfidenceTooWide.into()
+        );
+    }
+
+    fn feed_price(price: i64, expo: i32) -> FeedPrice {
+        FeedPrice {
+            price,
+            conf: 0,
+            expo,
+        }
+    }
+
+    #[test]
+    fn usd_conversion_with_negative_expo() {
+        // $3 at $150 per token with 9 decimals is 0.02 tokens
+        let feed = feed_price(15_000_000_000, -8);
+        assert_eq!(usd_cents_to_tokens(300, &feed, 9).unwrap(), 20_000_000);
+    }
+
+    #[test]
+    fn usd_conversion_with_zero_and_positive_expo() {
+        // $2.50 at $1 per token with 6 decimals
+        assert_eq!(
+            usd_cents_to_tokens(250, &feed_price(1, 0), 6).unwrap(),
+            2_500_000
+        );
+        // $10 at $20 (2 * 10^1) per token with 2 decimals is 0.5 tokens
+        assert_eq!(
+            usd_cents_to_tokens(1_000, &feed_price(2, 1), 2).unwrap(),
+            50
+        );
+    }
+
+    #[test]
+    fn usd_conversion_rounds_up() {
+        // $1 at $3 per token is a third of a token
+        let feed = feed_price(300_000_000, -8);
+        assert_eq!(usd_cents_to_tokens(100, &feed, 6).unwrap(), 333_334);
+        assert_eq!(usd_cents_to_tokens(100, &feed, 0).unwrap(), 1);
+        assert_eq!(usd_cents_to_tokens(0, &feed, 6).unwrap(), 0);
+    }
+
+    #[test]
+    fn usd_conversion_overflow() {
+        let feed = feed_price(1, -8);
+        assert_eq!(
+            usd_cents_to_tokens(u64::MAX, &feed, 18).unwrap_err(),
+            ErrorCode::MathOverflow.into()
+        );
+    }
+
+    fn leaderboard() -> Leaderboard {
+        Leaderboard {
+            recipient: Pubkey::new_unique(),
+            token_mint: Pubkey::new_unique(),
+            entries: Vec::new(),
+        }
+    }
+
+    fn totals(board: &Leaderboard) -> Vec<u64> {
+        board.entries.iter().map(|e| e.total).collect()
+    }
+
+    #[test]
+    fn leaderboard_keeps_entries_sorted() {
+        let mut board = leaderboard();
+        let (a, b, c) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        board.record(a, 10);
+        board.record(b, 30);
+        board.record(c, 20);
+        assert_eq!(totals(&board), vec![30, 20, 10]);
+
+        // A supporter's new running total replaces their entry
+        board.record(a, 40);
+        assert_eq!(totals(&board), vec![40, 30, 20]);
+        assert_eq!(board.entries[0].supporter, a);
+        assert_eq!(board.entries.len(), 3);
+    }
+
+    #[test]
+    fn leaderboard_evicts_the_lowest_entry_when_full() {
+        let mut board = leaderboard();
+        let supporters: Vec<Pubkey> = (0..LEADERBOARD_SIZE)
+            .map(|_| Pubkey::new_unique())
+            .collect();
+        for (i, supporter) in supporters.iter().enumerate() {
+            board.record(*supporter, (i as u64 + 1) * 10);
+        }
+        assert_eq!(board.entries.len(), LEADERBOARD_SIZE);
+
+        // Totals not above the lowest entry do not get on the board
+        let newcomer = Pubkey::new_unique();
+        board.record(newcomer, 10);
+        assert!(board.entries.iter().all(|e| e.supporter != newcomer));
+        assert_eq!(board.entries.last().unwrap().supporter, supporters[0]);
+
+        // A higher total replaces the lowest entry
+        board.record(newcomer, 15);
+        assert_eq!(board.entries.len(), LEADERBOARD_SIZE);
+        assert!(board.entries.iter().all(|e| e.supporter != supporters[0]));
+        assert_eq!(board.entries.last().unwrap().supporter, newcomer);
+    }
+
+    #[test]
+    fn leaderboard_updates_existing_entries_when_full() {
+        let mut board = leaderboard();
+        let supporters: Vec<Pubkey> = (0..LEADERBOARD_SIZE)
+            .map(|_| Pubkey::new_unique())
+            .collect();
+        for (i, supporter) in supporters.iter().enumerate() {
+            board.record(*supporter, (i as u64 + 1) * 10);
+        }
+
+        // The lowest supporter moving to the top evicts nobody
+        board.record(supporters[0], 1_000);
+        assert_eq!(board.entries.len(), LEADERBOARD_SIZE);
+        assert_eq!(board.entries[0].supporter, supporters[0]);
+        assert_eq!(board.entries.last().unwrap().supporter, supporters[1]);
+    }
+
+    fn paywall(price: u64, schedule: Option<PricingSchedule>) -> Paywall {
+        Paywall {
+            creator: Pubkey::new_unique(),
+            content_id: "content".to_string(),
+            price,
+            token_mint: Pubkey::new_unique(),
+            access_count: 0,
+            payees: Vec::new(),
+            refund_window: 0,
+            pending_settlements: 0,
+            schedule,
+            pay_what_you_want: false,
+            mint_prices: Vec::new(),
+            price_feed: None,
+            access_pass: false,
+            access_pass_bump: 0,
+            royalty_bps: 0,
+            granted_count: 0,
+            promo_count: 0,
+            closed: false,
+        }
+    }
+
+    #[test]
+    fn current_price_without_schedule() {
+        let paywall = paywall(1_000, None);
+        assert_eq!(paywall.current_price(paywall.token_mint, 0), Some(1_000));
+        assert_eq!(paywall.current_price(Pubkey::new_unique(), 0), None);
+    }
+
+    #[test]
+    fn early_bird_price_ends_at_until() {
+        let schedule = PricingSchedule::EarlyBird {
+            price: 600,
+            until: 100,
+        };
+        let paywall = paywall(1_000, Some(schedule));
+        let mint = paywall.token_mint;
+        assert_eq!(paywall.current_price(mint, 0), Some(600));
+        assert_eq!(paywall.current_price(mint, 99), Some(600));
+        assert_eq!(paywall.current_price(mint, 100), Some(1_000));
+        assert_eq!(paywall.current_price(mint, 101), Some(1_000));
+    }
+
+    #[test]
+    fn early_bird_scales_other_mint_prices() {
+        let schedule = PricingSchedule::EarlyBird {
+            price: 500,
+            until: 100,
+        };
+        let mut paywall = paywall(1_000, Some(schedule));
+        let other_mint = Pubkey::new_unique();
+        paywall.mint_prices.push(MintPrice {
+            token_mint: other_mint,
+            price: 333,
+        });
+        assert_eq!(paywall.current_price(other_mint, 0), Some(166));
+        assert_eq!(paywall.current_price(other_mint, 100), Some(333));
+    }
+
+    #[test]
+    fn decay_price_falls_linearly_to_free() {
+        let schedule = PricingSchedule::Decay {
+            start: 100,
+            duration: 1_000,
+        };
+        let paywall = paywall(1_000, Some(schedule));
+        let mint = paywall.token_mint;
+        assert_eq!(paywall.current_price(mint, 0), Some(1_000));
+        assert_eq!(paywall.current_price(mint, 100), Some(1_000));
+        assert_eq!(paywall.current_price(mint, 101), Some(999));
+        assert_eq!(paywall.current_price(mint, 600), Some(500));
+        assert_eq!(paywall.current_price(mint, 1_099), Some(1));
+        assert_eq!(paywall.current_price(mint, 1_100), Some(0));
+        assert_eq!(paywall.current_price(mint, i64::MAX), Some(0));
+    }
+
+    #[test]
+    fn decay_price_does_not_overflow_for_early_starts() {
+        let schedule = PricingSchedule::Decay {
+            start: i64::MIN,
+            duration: 1_000,
+        };
+        let paywall = paywall(1_000, Some(schedule));
+        assert_eq!(paywall.current_price(paywall.token_mint, 1), Some(0));
+    }
+
+    #[test]
+    fn decay_price_rounds_down() {
+        let schedule = PricingSchedule::Decay {
+            start: 0,
+            duration: 3,
+        };
+        let paywall = paywall(100, Some(schedule));
+        assert_eq!(paywall.current_price(paywall.token_mint, 1), Some(66));
+        assert_eq!(paywall.current_price(paywall.token_mint, 2), Some(33));
+    }
+
+    #[test]
+    fn pricing_schedule_validation() {
+        let early_bird = |price| PricingSchedule::EarlyBird { price, until: 100 };
+        assert!(early_bird(999).validate(1_000).is_ok());
+        assert_eq!(
+            early_bird(1_000).validate(1_000).unwrap_err(),
+            ErrorCode::InvalidPricingSchedule.into()
+        );
+        let decay = |duration| PricingSchedule::Decay { start: 0, duration };
+        assert!(decay(1).validate(1_000).is_ok());
+        assert_eq!(
+            decay(0).validate(1_000).unwrap_err(),
+            ErrorCode::InvalidPricingSchedule.into()
+        );
+        assert_eq!(
+            decay(-1).validate(1_000).unwrap_err(),
+            ErrorCode::InvalidPricingSchedule.into()
+        );
+        let negative_start = PricingSchedule::Decay {
+            start: -1,
+            duration: 1,
+        };
+        assert_eq!(
+            negative_start.validate(1_000).unwrap_err(),
+            ErrorCode::InvalidPricingSchedule.into()
+        );
+    }
 }
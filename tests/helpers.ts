@@ -0,0 +1,311 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import {
+  TOKEN_PROGRAM_ID,
+  createAccount,
+  createMint,
+  mintTo,
+} from "@solana/spl-token";
+import { assert } from "chai";
+import { NoiceSolana } from "../target/types/noice_solana";
+
+const { Keypair, LAMPORTS_PER_SOL, PublicKey, SystemProgram } = anchor.web3;
+
+export const BPF_LOADER_UPGRADEABLE_ID = new PublicKey(
+  "BPFLoaderUpgradeab1e11111111111111111111111"
+);
+
+export const PAYMENT_DECIMALS = 6;
+
+export function pda(
+  program: Program<NoiceSolana>,
+  seeds: (Buffer | Uint8Array)[]
+): anchor.web3.PublicKey {
+  return PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+}
+
+export function platformConfigAddress(program: Program<NoiceSolana>) {
+  return pda(program, [Buffer.from("platform_config")]);
+}
+
+export function paywallAddress(
+  program: Program<NoiceSolana>,
+  creator: anchor.web3.PublicKey,
+  contentId: string
+) {
+  return pda(program, [
+    Buffer.from("paywall"),
+    creator.toBuffer(),
+    Buffer.from(contentId),
+  ]);
+}
+
+export function receiptAddress(
+  program: Program<NoiceSolana>,
+  paywall: anchor.web3.PublicKey,
+  user: anchor.web3.PublicKey
+) {
+  return pda(program, [
+    Buffer.from("access_receipt"),
+    paywall.toBuffer(),
+    user.toBuffer(),
+  ]);
+}
+
+export function unlockVaultAddress(
+  program: Program<NoiceSolana>,
+  receipt: anchor.web3.PublicKey
+) {
+  return pda(program, [Buffer.from("unlock_vault"), receipt.toBuffer()]);
+}
+
+export function statsAddress(
+  program: Program<NoiceSolana>,
+  user: anchor.web3.PublicKey,
+  mint: anchor.web3.PublicKey
+) {
+  return pda(program, [
+    Buffer.from("mint_stats"),
+    user.toBuffer(),
+    mint.toBuffer(),
+  ]);
+}
+
+export function promoAddress(
+  program: Program<NoiceSolana>,
+  paywall: anchor.web3.PublicKey,
+  code: string
+) {
+  return pda(program, [
+    Buffer.from("promo"),
+    paywall.toBuffer(),
+    Buffer.from(code),
+  ]);
+}
+
+export function accessPassMintAddress(
+  program: Program<NoiceSolana>,
+  paywall: anchor.web3.PublicKey
+) {
+  return pda(program, [Buffer.from("access_pass"), paywall.toBuffer()]);
+}
+
+export function payer(provider: anchor.AnchorProvider): anchor.web3.Keypair {
+  return (provider.wallet as anchor.Wallet).payer;
+}
+
+// Initializes the platform config with the provider wallet as admin and fee
+// recipient, unless an earlier test file already did
+export async function ensurePlatformConfig(program: Program<NoiceSolana>) {
+  const provider = program.provider as anchor.AnchorProvider;
+  const platformConfig = platformConfigAddress(program);
+  if (await program.account.platformConfig.fetchNullable(platformConfig)) {
+    return platformConfig;
+  }
+  const programData = PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    BPF_LOADER_UPGRADEABLE_ID
+  )[0];
+  await program.methods
+    .initializeConfig(
+      250,
+      provider.wallet.publicKey,
+      new anchor.BN(0),
+      new anchor.BN(0)
+    )
+    .accounts({ programData })
+    .rpc();
+  return platformConfig;
+}
+
+export async function fundedKeypair(
+  provider: anchor.AnchorProvider,
+  sol = 10
+): Promise<anchor.web3.Keypair> {
+  const keypair = Keypair.generate();
+  const signature = await provider.connection.requestAirdrop(
+    keypair.publicKey,
+    sol * LAMPORTS_PER_SOL
+  );
+  const latestBlockhash = await provider.connection.getLatestBlockhash();
+  await provider.connection.confirmTransaction({
+    signature,
+    ...latestBlockhash,
+  });
+  return keypair;
+}
+
+export async function sleep(ms: number) {
+  return new Promise((resolve) => setTimeout(resolve, ms));
+}
+
+// A payment mint with a creator and the platform fee recipient's token
+// accounts, shared by the paywalls of a test file
+export interface PaymentSetup {
+  program: Program<NoiceSolana>;
+  provider: anchor.AnchorProvider;
+  platformConfig: anchor.web3.PublicKey;
+  mint: anchor.web3.PublicKey;
+  creator: anchor.web3.Keypair;
+  creatorTokenAccount: anchor.web3.PublicKey;
+  feeTokenAccount: anchor.web3.PublicKey;
+}
+
+export async function setupPayments(
+  program: Program<NoiceSolana>
+): Promise<PaymentSetup> {
+  const provider = program.provider as anchor.AnchorProvider;
+  const platformConfig = await ensurePlatformConfig(program);
+  const config = await program.account.platformConfig.fetch(platformConfig);
+  const creator = await fundedKeypair(provider);
+  const mint = await createMint(
+    provider.connection,
+    payer(provider),
+    provider.wallet.publicKey,
+    null,
+    PAYMENT_DECIMALS
+  );
+  const creatorTokenAccount = await createAccount(
+    provider.connection,
+    payer(provider),
+    mint,
+    creator.publicKey,
+    Keypair.generate()
+  );
+  const feeTokenAccount = await createAccount(
+    provider.connection,
+    payer(provider),
+    mint,
+    config.feeRecipient,
+    Keypair.generate()
+  );
+  return {
+    program,
+    provider,
+    platformConfig,
+    mint,
+    creator,
+    creatorTokenAccount,
+    feeTokenAccount,
+  };
+}
+
+// A funded buyer holding `amount` of the payment mint
+export async function buyer(setup: PaymentSetup, amount: number) {
+  const { provider, mint } = setup;
+  const user = await fundedKeypair(provider);
+  const tokenAccount = await createAccount(
+    provider.connection,
+    payer(provider),
+    mint,
+    user.publicKey,
+    Keypair.generate()
+  );
+  await mintTo(
+    provider.connection,
+    payer(provider),
+    mint,
+    tokenAccount,
+    provider.wallet.publicKey,
+    amount
+  );
+  return { user, tokenAccount };
+}
+
+export interface PaywallOptions {
+  price: number;
+  refundWindow?: number;
+  payWhatYouWant?: boolean;
+}
+
+export async function createPaywall(
+  setup: PaymentSetup,
+  contentId: string,
+  options: PaywallOptions
+) {
+  const { program, creator, mint } = setup;
+  await program.methods
+    .createPaywall(
+      contentId,
+      new anchor.BN(options.price),
+      mint,
+      [],
+      new anchor.BN(options.refundWindow ?? 0),
+      options.payWhatYouWant ?? false
+    )
+    .accountsPartial({ creator: creator.publicKey })
+    .signers([creator])
+    .rpc();
+  return paywallAddress(program, creator.publicKey, contentId);
+}
+
+// Accounts of the unlock_paywall variants; optional accounts default to
+// none and the receipt belongs to the beneficiary for gifts
+export function unlockAccounts(
+  setup: PaymentSetup,
+  paywall: anchor.web3.PublicKey,
+  user: anchor.web3.PublicKey,
+  userTokenAccount: anchor.web3.PublicKey,
+  overrides: { [name: string]: anchor.web3.PublicKey | null } = {}
+) {
+  const { program, platformConfig, mint, creator } = setup;
+  const holder = overrides.beneficiary ?? user;
+  return {
+    paywall,
+    accessReceipt: receiptAddress(program, paywall, holder),
+    unlockVault: null,
+    platformConfig,
+    userTokenAccount,
+    creatorTokenAccount: setup.creatorTokenAccount,
+    feeTokenAccount: setup.feeTokenAccount,
+    userStats: statsAddress(program, user, mint),
+    creatorStats: statsAddress(program, creator.publicKey, mint),
+    creatorProfile: null,
+    priceFeed: null,
+    accessPassMint: null,
+    userPassAccount: null,
+    passTokenProgram: null,
+    beneficiary: null,
+    user,
+    tokenMint: mint,
+    tokenProgram: TOKEN_PROGRAM_ID,
+    systemProgram: SystemProgram.programId,
+    ...overrides,
+  };
+}
+
+// Asserts that a transaction fails with the given program error
+export async function expectError(promise: Promise<unknown>, code: string) {
+  try {
+    await promise;
+  } catch (err) {
+    assert.instanceOf(err, anchor.AnchorError);
+    assert.equal((err as anchor.AnchorError).error.errorCode.code, code);
+    return;
+  }
+  assert.fail(`Expected the transaction to fail with ${code}`);
+}
+
+export async function createPromo(
+  setup: PaymentSetup,
+  contentId: string,
+  code: string,
+  discountPercent: number,
+  maxRedemptions = 0
+) {
+  const { program, creator } = setup;
+  const paywall = paywallAddress(program, creator.publicKey, contentId);
+  const promo = promoAddress(program, paywall, code);
+  await program.methods
+    .createPromo(
+      contentId,
+      code,
+      discountPercent,
+      maxRedemptions,
+      new anchor.BN(0)
+    )
+    .accountsPartial({ paywall, promo, creator: creator.publicKey })
+    .signers([creator])
+    .rpc();
+  return promo;
+}
@@ -1,16 +1,41 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
+import { assert } from "chai";
 import { NoiceSolana } from "../target/types/noice_solana";
+import { BPF_LOADER_UPGRADEABLE_ID } from "./helpers";
+
+const { PublicKey } = anchor.web3;
 
 describe("noice-solana", () => {
   // Configure the client to use the local cluster.
-  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
 
   const program = anchor.workspace.NoiceSolana as Program<NoiceSolana>;
 
-  it("Is initialized!", async () => {
-    // Add your test here.
-    const tx = await program.methods.initialize().rpc();
-    console.log("Your transaction signature", tx);
+  it("Initializes the platform config from the upgrade authority", async () => {
+    const [programData] = PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      BPF_LOADER_UPGRADEABLE_ID
+    );
+
+    await program.methods
+      .initializeConfig(
+        250,
+        provider.wallet.publicKey,
+        new anchor.BN(0),
+        new anchor.BN(0)
+      )
+      .accounts({ programData })
+      .rpc();
+
+    const [platformConfig] = PublicKey.findProgramAddressSync(
+      [Buffer.from("platform_config")],
+      program.programId
+    );
+    const config = await program.account.platformConfig.fetch(platformConfig);
+    assert.ok(config.admin.equals(provider.wallet.publicKey));
+    assert.equal(config.feeBps, 250);
+    assert.isFalse(config.paused);
   });
 });
@@ -0,0 +1,181 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import {
+  TOKEN_2022_PROGRAM_ID,
+  createAssociatedTokenAccount,
+  getAccount,
+  getTokenMetadata,
+  transferChecked,
+} from "@solana/spl-token";
+import { assert } from "chai";
+import { NoiceSolana } from "../target/types/noice_solana";
+import {
+  PaymentSetup,
+  accessPassMintAddress,
+  buyer,
+  createPaywall,
+  expectError,
+  fundedKeypair,
+  payer,
+  receiptAddress,
+  setupPayments,
+  unlockAccounts,
+} from "./helpers";
+
+describe("paywall access passes", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.NoiceSolana as Program<NoiceSolana>;
+  const contentId = "access-pass";
+  let setup: PaymentSetup;
+  let paywall: anchor.web3.PublicKey;
+  let passMint: anchor.web3.PublicKey;
+
+  async function passAccount(owner: anchor.web3.PublicKey) {
+    return createAssociatedTokenAccount(
+      provider.connection,
+      payer(provider),
+      passMint,
+      owner,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+  }
+
+  async function passBalance(account: anchor.web3.PublicKey) {
+    const pass = await getAccount(
+      provider.connection,
+      account,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    return Number(pass.amount);
+  }
+
+  function verifyAccessPass(
+    holder: anchor.web3.PublicKey,
+    holderPassAccount: anchor.web3.PublicKey
+  ) {
+    return program.methods
+      .verifyAccessPass(contentId)
+      .accountsPartial({
+        paywall,
+        accessPassMint: passMint,
+        holderPassAccount,
+        holder,
+      })
+      .rpc();
+  }
+
+  before(async () => {
+    setup = await setupPayments(program);
+    paywall = await createPaywall(setup, contentId, { price: 1_000_000 });
+    passMint = accessPassMintAddress(program, paywall);
+
+    await program.methods
+      .enableAccessPass(
+        contentId,
+        500,
+        "Access Pass",
+        "PASS",
+        "https://example.com/pass.json"
+      )
+      .accountsPartial({
+        paywall,
+        accessPassMint: passMint,
+        creator: setup.creator.publicKey,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([setup.creator])
+      .rpc();
+  });
+
+  it("Records the royalty and creator in the pass metadata", async () => {
+    const metadata = await getTokenMetadata(
+      provider.connection,
+      passMint,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    assert.equal(metadata.name, "Access Pass");
+    assert.equal(metadata.symbol, "PASS");
+    assert.deepEqual(metadata.additionalMetadata, [
+      ["seller_fee_basis_points", "500"],
+      ["creator", setup.creator.publicKey.toBase58()],
+    ]);
+  });
+
+  it("Mints a pass to the buyer, which carries access once sold", async () => {
+    const { user, tokenAccount } = await buyer(setup, 10_000_000);
+    const userPassAccount = await passAccount(user.publicKey);
+
+    await program.methods
+      .unlockPaywall(contentId, new BN(1_000_000))
+      .accountsPartial(
+        unlockAccounts(setup, paywall, user.publicKey, tokenAccount, {
+          accessPassMint: passMint,
+          userPassAccount,
+          passTokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+      )
+      .signers([user])
+      .rpc();
+
+    assert.equal(await passBalance(userPassAccount), 1);
+    const receipt = receiptAddress(program, paywall, user.publicKey);
+    const receiptAccount = await program.account.accessReceipt.fetch(receipt);
+    assert.isTrue(receiptAccount.passMinted);
+    await verifyAccessPass(user.publicKey, userPassAccount);
+
+    // The receipt does not grant access on its own, as the pass may be sold
+    await expectError(
+      program.methods
+        .verifyAccess(contentId)
+        .accountsPartial({
+          paywall,
+          accessReceipt: receipt,
+          user: user.publicKey,
+        })
+        .rpc(),
+      "AccessHeldByPass"
+    );
+
+    const secondHolder = await fundedKeypair(provider);
+    const secondPassAccount = await passAccount(secondHolder.publicKey);
+    await transferChecked(
+      provider.connection,
+      payer(provider),
+      userPassAccount,
+      passMint,
+      secondPassAccount,
+      user,
+      1,
+      0,
+      [],
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    await verifyAccessPass(secondHolder.publicKey, secondPassAccount);
+    await expectError(
+      verifyAccessPass(user.publicKey, userPassAccount),
+      "AccessNotFound"
+    );
+  });
+
+  it("Requires the pass accounts on unlock", async () => {
+    const { user, tokenAccount } = await buyer(setup, 10_000_000);
+
+    await expectError(
+      program.methods
+        .unlockPaywall(contentId, new BN(1_000_000))
+        .accountsPartial(
+          unlockAccounts(setup, paywall, user.publicKey, tokenAccount)
+        )
+        .signers([user])
+        .rpc(),
+      "InvalidAccessPass"
+    );
+  });
+});
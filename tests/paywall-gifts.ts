@@ -0,0 +1,134 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import { getAccount } from "@solana/spl-token";
+import { assert } from "chai";
+import { NoiceSolana } from "../target/types/noice_solana";
+import {
+  PaymentSetup,
+  buyer,
+  createPaywall,
+  createPromo,
+  expectError,
+  receiptAddress,
+  setupPayments,
+  unlockAccounts,
+} from "./helpers";
+
+const { Keypair } = anchor.web3;
+
+describe("paywall gifts", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.NoiceSolana as Program<NoiceSolana>;
+  let setup: PaymentSetup;
+
+  before(async () => {
+    setup = await setupPayments(program);
+  });
+
+  it("Issues the receipt to the beneficiary and pays out at once", async () => {
+    // Gifts skip the refund escrow even on refundable paywalls
+    const paywall = await createPaywall(setup, "gift", {
+      price: 1_000_000,
+      refundWindow: 3600,
+    });
+    const { user, tokenAccount } = await buyer(setup, 10_000_000);
+    const beneficiary = Keypair.generate().publicKey;
+    const creatorBefore = await getAccount(
+      provider.connection,
+      setup.creatorTokenAccount
+    );
+
+    await program.methods
+      .giftUnlock("gift", new BN(1_000_000))
+      .accountsPartial(
+        unlockAccounts(setup, paywall, user.publicKey, tokenAccount, {
+          beneficiary,
+        })
+      )
+      .signers([user])
+      .rpc();
+
+    const receipt = await program.account.accessReceipt.fetch(
+      receiptAddress(program, paywall, beneficiary)
+    );
+    assert.ok(receipt.user.equals(beneficiary));
+    assert.ok(receipt.payer.equals(user.publicKey));
+    assert.deepEqual(receipt.kind, { gift: {} });
+    assert.isTrue(receipt.settled);
+    assert.isNull(
+      await program.account.accessReceipt.fetchNullable(
+        receiptAddress(program, paywall, user.publicKey)
+      )
+    );
+
+    const creatorAfter = await getAccount(
+      provider.connection,
+      setup.creatorTokenAccount
+    );
+    // 2.5% platform fee
+    assert.equal(
+      Number(creatorAfter.amount) - Number(creatorBefore.amount),
+      975_000
+    );
+
+    await program.methods
+      .verifyAccess("gift")
+      .accountsPartial({
+        paywall,
+        accessReceipt: receiptAddress(program, paywall, beneficiary),
+        user: beneficiary,
+      })
+      .rpc();
+  });
+
+  it("Refuses gifts to the buyer or the creator", async () => {
+    const paywall = await createPaywall(setup, "gift-invalid", {
+      price: 1_000_000,
+    });
+    const { user, tokenAccount } = await buyer(setup, 10_000_000);
+
+    for (const beneficiary of [user.publicKey, setup.creator.publicKey]) {
+      await expectError(
+        program.methods
+          .giftUnlock("gift-invalid", new BN(1_000_000))
+          .accountsPartial(
+            unlockAccounts(setup, paywall, user.publicKey, tokenAccount, {
+              beneficiary,
+            })
+          )
+          .signers([user])
+          .rpc(),
+        "InvalidBeneficiary"
+      );
+    }
+  });
+
+  it("Refuses a promo unlock gifted to the buyer", async () => {
+    const paywall = await createPaywall(setup, "gift-promo", {
+      price: 1_000_000,
+      refundWindow: 3600,
+    });
+    const promo = await createPromo(setup, "gift-promo", "GIFT", 50);
+    const { user, tokenAccount } = await buyer(setup, 10_000_000);
+
+    await expectError(
+      program.methods
+        .unlockPaywallWithPromo("gift-promo", "GIFT", new BN(500_000))
+        .accountsPartial({
+          unlock: unlockAccounts(
+            setup,
+            paywall,
+            user.publicKey,
+            tokenAccount,
+            { beneficiary: user.publicKey }
+          ),
+          promo,
+        })
+        .signers([user])
+        .rpc(),
+      "InvalidBeneficiary"
+    );
+  });
+});
@@ -0,0 +1,119 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import { assert } from "chai";
+import { NoiceSolana } from "../target/types/noice_solana";
+import {
+  PaymentSetup,
+  buyer,
+  createPaywall,
+  expectError,
+  receiptAddress,
+  setupPayments,
+  unlockAccounts,
+} from "./helpers";
+
+const { Keypair, SystemProgram, Transaction } = anchor.web3;
+
+describe("paywall access grants", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.NoiceSolana as Program<NoiceSolana>;
+  const contentId = "grants";
+  let setup: PaymentSetup;
+  let paywall: anchor.web3.PublicKey;
+
+  function grantAccess(users: anchor.web3.PublicKey[]) {
+    return program.methods
+      .grantAccess(contentId, users)
+      .accountsPartial({ paywall, creator: setup.creator.publicKey })
+      .remainingAccounts(
+        users.map((user) => ({
+          pubkey: receiptAddress(program, paywall, user),
+          isWritable: true,
+          isSigner: false,
+        }))
+      )
+      .signers([setup.creator])
+      .rpc();
+  }
+
+  function revokeAccess(user: anchor.web3.PublicKey) {
+    return program.methods
+      .revokeAccess(contentId)
+      .accountsPartial({
+        paywall,
+        accessReceipt: receiptAddress(program, paywall, user),
+        user,
+        creator: setup.creator.publicKey,
+      })
+      .signers([setup.creator])
+      .rpc();
+  }
+
+  before(async () => {
+    setup = await setupPayments(program);
+    paywall = await createPaywall(setup, contentId, { price: 1_000_000 });
+  });
+
+  it("Grants access, including at pre-funded receipt addresses", async () => {
+    const first = Keypair.generate().publicKey;
+    const second = Keypair.generate().publicKey;
+
+    // Lamports sent to a receipt address must not block the grant
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: provider.wallet.publicKey,
+          toPubkey: receiptAddress(program, paywall, second),
+          lamports: 1_000_000,
+        })
+      )
+    );
+
+    await grantAccess([first, second]);
+
+    for (const user of [first, second]) {
+      const receipt = await program.account.accessReceipt.fetch(
+        receiptAddress(program, paywall, user)
+      );
+      assert.ok(receipt.user.equals(user));
+      assert.deepEqual(receipt.kind, { grant: {} });
+      assert.equal(receipt.amount.toNumber(), 0);
+    }
+    let paywallAccount = await program.account.paywall.fetch(paywall);
+    assert.equal(paywallAccount.grantedCount.toNumber(), 2);
+
+    // Wallets that already hold access are skipped
+    await grantAccess([first]);
+    paywallAccount = await program.account.paywall.fetch(paywall);
+    assert.equal(paywallAccount.grantedCount.toNumber(), 2);
+
+    await revokeAccess(first);
+    assert.isNull(
+      await program.account.accessReceipt.fetchNullable(
+        receiptAddress(program, paywall, first)
+      )
+    );
+    paywallAccount = await program.account.paywall.fetch(paywall);
+    assert.equal(paywallAccount.grantedCount.toNumber(), 1);
+  });
+
+  it("Refuses to revoke paid access", async () => {
+    const { user, tokenAccount } = await buyer(setup, 10_000_000);
+    await program.methods
+      .unlockPaywall(contentId, new BN(1_000_000))
+      .accountsPartial(
+        unlockAccounts(setup, paywall, user.publicKey, tokenAccount)
+      )
+      .signers([user])
+      .rpc();
+
+    await expectError(revokeAccess(user.publicKey), "InvalidGrant");
+    assert.isNotNull(
+      await program.account.accessReceipt.fetchNullable(
+        receiptAddress(program, paywall, user.publicKey)
+      )
+    );
+  });
+});
@@ -0,0 +1,94 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import { getAccount } from "@solana/spl-token";
+import { assert } from "chai";
+import { NoiceSolana } from "../target/types/noice_solana";
+import {
+  PaymentSetup,
+  buyer,
+  createPaywall,
+  createPromo,
+  expectError,
+  receiptAddress,
+  setupPayments,
+  unlockAccounts,
+} from "./helpers";
+
+describe("paywall promos", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.NoiceSolana as Program<NoiceSolana>;
+  let setup: PaymentSetup;
+
+  async function unlockWithPromo(
+    paywall: anchor.web3.PublicKey,
+    contentId: string,
+    code: string,
+    promo: anchor.web3.PublicKey,
+    maxAmount: number
+  ) {
+    const { user, tokenAccount } = await buyer(setup, 10_000_000);
+    await program.methods
+      .unlockPaywallWithPromo(contentId, code, new BN(maxAmount))
+      .accountsPartial({
+        unlock: unlockAccounts(setup, paywall, user.publicKey, tokenAccount),
+        promo,
+      })
+      .signers([user])
+      .rpc();
+    return { user, tokenAccount };
+  }
+
+  before(async () => {
+    setup = await setupPayments(program);
+  });
+
+  it("Unlocks at the discounted price and counts the redemption", async () => {
+    const paywall = await createPaywall(setup, "promo", { price: 1_000_000 });
+    const promo = await createPromo(setup, "promo", "LAUNCH", 25);
+
+    const { user, tokenAccount } = await unlockWithPromo(
+      paywall,
+      "promo",
+      "LAUNCH",
+      promo,
+      750_000
+    );
+
+    const receipt = await program.account.accessReceipt.fetch(
+      receiptAddress(program, paywall, user.publicKey)
+    );
+    assert.equal(receipt.amount.toNumber(), 750_000);
+    assert.ok(receipt.promo.equals(promo));
+    const account = await getAccount(provider.connection, tokenAccount);
+    assert.equal(Number(account.amount), 9_250_000);
+    assert.equal((await program.account.promo.fetch(promo)).redemptions, 1);
+  });
+
+  it("Refuses a discounted price above the user's maximum", async () => {
+    const paywall = await createPaywall(setup, "promo-max", {
+      price: 1_000_000,
+    });
+    const promo = await createPromo(setup, "promo-max", "LAUNCH", 25);
+
+    await expectError(
+      unlockWithPromo(paywall, "promo-max", "LAUNCH", promo, 749_999),
+      "AmountBelowPrice"
+    );
+    assert.equal((await program.account.promo.fetch(promo)).redemptions, 0);
+  });
+
+  it("Refuses redemptions past the maximum", async () => {
+    const paywall = await createPaywall(setup, "promo-limit", {
+      price: 1_000_000,
+    });
+    const promo = await createPromo(setup, "promo-limit", "ONCE", 50, 1);
+
+    await unlockWithPromo(paywall, "promo-limit", "ONCE", promo, 500_000);
+    await expectError(
+      unlockWithPromo(paywall, "promo-limit", "ONCE", promo, 500_000),
+      "PromoExhausted"
+    );
+  });
+});
@@ -0,0 +1,185 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import { TOKEN_PROGRAM_ID, getAccount } from "@solana/spl-token";
+import { assert } from "chai";
+import { NoiceSolana } from "../target/types/noice_solana";
+import {
+  PaymentSetup,
+  buyer,
+  createPaywall,
+  createPromo,
+  expectError,
+  receiptAddress,
+  setupPayments,
+  sleep,
+  statsAddress,
+  unlockAccounts,
+  unlockVaultAddress,
+} from "./helpers";
+
+describe("paywall refunds", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.NoiceSolana as Program<NoiceSolana>;
+  let setup: PaymentSetup;
+
+  // Accounts of an escrowed unlock, with the vault of the user's receipt
+  function escrowedUnlockAccounts(
+    paywall: anchor.web3.PublicKey,
+    user: anchor.web3.PublicKey,
+    tokenAccount: anchor.web3.PublicKey
+  ) {
+    const receipt = receiptAddress(program, paywall, user);
+    return unlockAccounts(setup, paywall, user, tokenAccount, {
+      unlockVault: unlockVaultAddress(program, receipt),
+    });
+  }
+
+  function settleAccounts(
+    paywall: anchor.web3.PublicKey,
+    user: anchor.web3.PublicKey
+  ) {
+    const receipt = receiptAddress(program, paywall, user);
+    return {
+      paywall,
+      accessReceipt: receipt,
+      unlockVault: unlockVaultAddress(program, receipt),
+      platformConfig: setup.platformConfig,
+      creatorTokenAccount: setup.creatorTokenAccount,
+      feeTokenAccount: setup.feeTokenAccount,
+      user,
+      tokenMint: setup.mint,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+  }
+
+  async function balance(tokenAccount: anchor.web3.PublicKey) {
+    return Number((await getAccount(provider.connection, tokenAccount)).amount);
+  }
+
+  before(async () => {
+    setup = await setupPayments(program);
+  });
+
+  it("Refunds a promo unlock, reverting stats and the redemption", async () => {
+    const paywall = await createPaywall(setup, "refund", {
+      price: 1_000_000,
+      refundWindow: 3600,
+    });
+    const promo = await createPromo(setup, "refund", "HALF", 50, 1);
+    const { user, tokenAccount } = await buyer(setup, 10_000_000);
+    const creatorStats = statsAddress(
+      program,
+      setup.creator.publicKey,
+      setup.mint
+    );
+    const userStats = statsAddress(program, user.publicKey, setup.mint);
+
+    await program.methods
+      .unlockPaywallWithPromo("refund", "HALF", new BN(500_000))
+      .accountsPartial({
+        unlock: escrowedUnlockAccounts(paywall, user.publicKey, tokenAccount),
+        promo,
+      })
+      .signers([user])
+      .rpc();
+
+    const receipt = receiptAddress(program, paywall, user.publicKey);
+    const vault = unlockVaultAddress(program, receipt);
+    assert.equal(await balance(vault), 500_000);
+    assert.equal(await balance(tokenAccount), 9_500_000);
+    assert.equal((await program.account.promo.fetch(promo)).redemptions, 1);
+    const creatorStatsBefore = await program.account.mintStats.fetch(
+      creatorStats
+    );
+
+    await program.methods
+      .requestRefund("refund")
+      .accountsPartial({
+        paywall,
+        accessReceipt: receipt,
+        unlockVault: vault,
+        userTokenAccount: tokenAccount,
+        userStats,
+        creatorStats,
+        creatorProfile: null,
+        promo,
+        user: user.publicKey,
+        tokenMint: setup.mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+    assert.isNull(await program.account.accessReceipt.fetchNullable(receipt));
+    assert.isNull(await provider.connection.getAccountInfo(vault));
+    assert.equal(await balance(tokenAccount), 10_000_000);
+
+    const sent = await program.account.mintStats.fetch(userStats);
+    assert.equal(sent.totalSent.toNumber(), 0);
+    assert.equal(sent.sentCount.toNumber(), 0);
+    const received = await program.account.mintStats.fetch(creatorStats);
+    assert.equal(
+      received.totalReceived.toNumber(),
+      creatorStatsBefore.totalReceived.toNumber() - 500_000
+    );
+    assert.equal(
+      received.receivedCount.toNumber(),
+      creatorStatsBefore.receivedCount.toNumber() - 1
+    );
+
+    assert.equal((await program.account.promo.fetch(promo)).redemptions, 0);
+    const paywallAccount = await program.account.paywall.fetch(paywall);
+    assert.equal(paywallAccount.accessCount.toNumber(), 0);
+    assert.equal(paywallAccount.pendingSettlements.toNumber(), 0);
+  });
+
+  it("Settles an unlock to the creator and platform after the window", async () => {
+    const paywall = await createPaywall(setup, "settle", {
+      price: 1_000_000,
+      refundWindow: 5,
+    });
+    const { user, tokenAccount } = await buyer(setup, 10_000_000);
+
+    await program.methods
+      .unlockPaywall("settle", new BN(1_000_000))
+      .accountsPartial(
+        escrowedUnlockAccounts(paywall, user.publicKey, tokenAccount)
+      )
+      .signers([user])
+      .rpc();
+
+    await expectError(
+      program.methods
+        .settleUnlock("settle")
+        .accountsPartial(settleAccounts(paywall, user.publicKey))
+        .rpc(),
+      "RefundWindowOpen"
+    );
+
+    const creatorBefore = await balance(setup.creatorTokenAccount);
+    const feeBefore = await balance(setup.feeTokenAccount);
+    await sleep(7000);
+    await program.methods
+      .settleUnlock("settle")
+      .accountsPartial(settleAccounts(paywall, user.publicKey))
+      .rpc();
+
+    const receipt = receiptAddress(program, paywall, user.publicKey);
+    assert.isTrue((await program.account.accessReceipt.fetch(receipt)).settled);
+    assert.isNull(
+      await provider.connection.getAccountInfo(
+        unlockVaultAddress(program, receipt)
+      )
+    );
+    // 2.5% platform fee
+    assert.equal(
+      await balance(setup.creatorTokenAccount),
+      creatorBefore + 975_000
+    );
+    assert.equal(await balance(setup.feeTokenAccount), feeBefore + 25_000);
+    const paywallAccount = await program.account.paywall.fetch(paywall);
+    assert.equal(paywallAccount.pendingSettlements.toNumber(), 0);
+  });
+});
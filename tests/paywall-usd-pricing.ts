@@ -0,0 +1,156 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import { getAccount } from "@solana/spl-token";
+import { assert } from "chai";
+import { MockPyth } from "../target/types/mock_pyth";
+import { NoiceSolana } from "../target/types/noice_solana";
+import {
+  PaymentSetup,
+  buyer,
+  createPaywall,
+  expectError,
+  receiptAddress,
+  setupPayments,
+  unlockAccounts,
+} from "./helpers";
+
+const { Keypair } = anchor.web3;
+
+// Needs the `mock-oracle` build from `yarn test`
+describe("paywall usd pricing", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.NoiceSolana as Program<NoiceSolana>;
+  const mockPyth = anchor.workspace.MockPyth as Program<MockPyth>;
+  const priceFeed = Keypair.generate();
+  let setup: PaymentSetup;
+
+  // Writes a token price in USD with 8 decimals, published `age` seconds ago
+  async function writePrice(price: number, age = 0) {
+    const now = await provider.connection.getBlockTime(
+      await provider.connection.getSlot()
+    );
+    await mockPyth.methods
+      .writePriceFeed(new BN(price), new BN(0), -8, new BN(now - age))
+      .accountsPartial({
+        priceFeed: priceFeed.publicKey,
+        payer: provider.wallet.publicKey,
+      })
+      .signers([priceFeed])
+      .rpc();
+  }
+
+  async function usdPaywall(contentId: string, usdCents: number) {
+    const paywall = await createPaywall(setup, contentId, { price: 1 });
+    await program.methods
+      .setUsdPrice(contentId, new BN(usdCents), priceFeed.publicKey)
+      .accountsPartial({ paywall, creator: setup.creator.publicKey })
+      .signers([setup.creator])
+      .rpc();
+    return paywall;
+  }
+
+  async function setMaxPaywallPrice(maxPaywallPrice: number) {
+    await program.methods
+      .updateLimits(new BN(0), new BN(maxPaywallPrice))
+      .accountsPartial({
+        platformConfig: setup.platformConfig,
+        admin: provider.wallet.publicKey,
+      })
+      .rpc();
+  }
+
+  before(async () => {
+    setup = await setupPayments(program);
+    // $2 per token
+    await writePrice(200_000_000);
+  });
+
+  it("Unlocks at the price converted through the feed", async () => {
+    const paywall = await usdPaywall("usd-convert", 300);
+    const { user, tokenAccount } = await buyer(setup, 10_000_000);
+
+    // $3 at $2 per token is 1.5 tokens
+    await program.methods
+      .unlockPaywall("usd-convert", new BN(1_500_000))
+      .accountsPartial(
+        unlockAccounts(setup, paywall, user.publicKey, tokenAccount, {
+          priceFeed: priceFeed.publicKey,
+        })
+      )
+      .signers([user])
+      .rpc();
+
+    const receipt = await program.account.accessReceipt.fetch(
+      receiptAddress(program, paywall, user.publicKey)
+    );
+    assert.equal(receipt.amount.toNumber(), 1_500_000);
+    const account = await getAccount(provider.connection, tokenAccount);
+    assert.equal(Number(account.amount), 8_500_000);
+  });
+
+  it("Refuses a converted price above the user's maximum", async () => {
+    const paywall = await usdPaywall("usd-max-amount", 300);
+    const { user, tokenAccount } = await buyer(setup, 10_000_000);
+
+    await expectError(
+      program.methods
+        .unlockPaywall("usd-max-amount", new BN(1_499_999))
+        .accountsPartial(
+          unlockAccounts(setup, paywall, user.publicKey, tokenAccount, {
+            priceFeed: priceFeed.publicKey,
+          })
+        )
+        .signers([user])
+        .rpc(),
+      "AmountBelowPrice"
+    );
+  });
+
+  it("Refuses a converted price above the maximum paywall price", async () => {
+    const paywall = await usdPaywall("usd-limit", 300);
+    const { user, tokenAccount } = await buyer(setup, 10_000_000);
+
+    await setMaxPaywallPrice(1_000_000);
+    try {
+      await expectError(
+        program.methods
+          .unlockPaywall("usd-limit", new BN(1_500_000))
+          .accountsPartial(
+            unlockAccounts(setup, paywall, user.publicKey, tokenAccount, {
+              priceFeed: priceFeed.publicKey,
+            })
+          )
+          .signers([user])
+          .rpc(),
+        "AmountExceedsLimit"
+      );
+    } finally {
+      await setMaxPaywallPrice(0);
+    }
+  });
+
+  it("Refuses stale prices", async () => {
+    const paywall = await usdPaywall("usd-stale", 300);
+    const { user, tokenAccount } = await buyer(setup, 10_000_000);
+
+    await writePrice(200_000_000, 120);
+    try {
+      await expectError(
+        program.methods
+          .unlockPaywall("usd-stale", new BN(1_500_000))
+          .accountsPartial(
+            unlockAccounts(setup, paywall, user.publicKey, tokenAccount, {
+              priceFeed: priceFeed.publicKey,
+            })
+          )
+          .signers([user])
+          .rpc(),
+        "StalePrice"
+      );
+    } finally {
+      await writePrice(200_000_000);
+    }
+  });
+});